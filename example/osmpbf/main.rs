@@ -1,5 +1,4 @@
 use georender_pack::{encode,Member,MemberType,MemberRole};
-use hex;
 use osmpbf::{Element, ElementReader};
 use std::collections::HashMap;
 use std::env;
@@ -17,25 +16,25 @@ fn main() -> Result<(), Box<dyn Error>> {
             Element::DenseNode(node) => {
                 let point = (node.lon() as f32, node.lat() as f32);
                 nodes.insert(node.id as u64, point);
-                let tags = node.tags().into_iter().collect::<Vec<_>>();
+                let tags = node.tags().collect::<Vec<_>>();
                 let encoded = encode::node(node.id as u64, point, &tags).unwrap();
                 println!("{}", hex::encode(encoded));
             },
             Element::Node(node) => {
                 let point = (node.lon() as f32, node.lat() as f32);
                 nodes.insert(node.id() as u64, point);
-                let tags = node.tags().into_iter().collect::<Vec<_>>();
+                let tags = node.tags().collect::<Vec<_>>();
                 let encoded = encode::node(node.id() as u64, point, &tags).unwrap();
                 println!("{}", hex::encode(encoded));
             },
             Element::Relation(rel) => {
-                let tags = rel.tags().into_iter().collect::<Vec<_>>();
+                let tags = rel.tags().collect::<Vec<_>>();
                 let members = rel.members().map(|m| convert_member(&m)).collect::<Vec<_>>();
                 let encoded = encode::relation(rel.id() as u64, &tags, &members, &nodes, &ways).unwrap();
                 println!("{}", hex::encode(encoded));
             },
             Element::Way(way) => {
-                let tags = way.tags().into_iter().collect::<Vec<_>>();
+                let tags = way.tags().collect::<Vec<_>>();
                 let refs = way.refs().map(|r| r as u64).collect::<Vec<u64>>();
                 ways.insert(way.id() as u64, refs.clone());
                 let encoded = encode::way(way.id() as u64, &tags, &refs, &nodes).unwrap();
@@ -0,0 +1,73 @@
+#[test]
+fn unwrap_ring() {
+    let mut positions: Vec<f32> = vec![
+        179.0, 0.0, -179.0, 0.0, -179.0, 1.0, 179.0, 1.0,
+        179.5, 0.5, -179.5, 0.5, -179.5, 0.8,
+    ];
+    assert!(crosses(&positions[0..8]));
+    unwrap(&mut positions, &[4]);
+    assert_eq!(positions, vec![
+        179.0, 0.0, 181.0, 0.0, 181.0, 1.0, 179.0, 1.0,
+        179.5, 0.5, 180.5, 0.5, 180.5, 0.8,
+    ]);
+    assert!(!crosses(&positions));
+    // a hole that starts on the far side is moved next to its outer ring
+    let mut positions: Vec<f32> = vec![
+        179.0, 0.0, -179.0, 0.0, -179.0, 1.0, 179.0, 1.0,
+        -179.5, 0.5, -179.5, 0.8, 179.5, 0.5,
+    ];
+    unwrap(&mut positions, &[4]);
+    assert_eq!(&positions[8..], &[180.5, 0.5, 180.5, 0.8, 179.5, 0.5]);
+}
+
+/// Whether consecutive positions jump more than 180° of longitude, which
+/// means the path crosses the antimeridian rather than going the long way.
+pub fn crosses(positions: &[f32]) -> bool {
+    positions.chunks_exact(2).zip(positions.chunks_exact(2).skip(1))
+        .any(|(a,b)| (b[0] - a[0]).abs() > 180.0)
+}
+
+/// Unwrap longitudes so no step between consecutive positions is more than
+/// 180°, shifting points past the antimeridian by 360°. The first position
+/// keeps its longitude, so longitudes may end up outside ±180°. `holes` holds
+/// the position index each later ring starts at, as passed to `Area::push`;
+/// each ring is unwrapped on its own and then moved by a multiple of 360° to
+/// sit next to the first ring.
+pub fn unwrap(positions: &mut [f32], holes: &[usize]) {
+    if let Some(reference) = positions.first() {
+        unwrap_near(positions, holes, *reference);
+    }
+}
+
+/// Like `unwrap`, but every ring, the first included, is moved to sit next to
+/// the longitude `reference`. Use it to keep the polygons of one feature,
+/// such as the outer rings of a multipolygon, in the same frame.
+pub fn unwrap_near(positions: &mut [f32], holes: &[usize], reference: f32) {
+    let n = positions.len() / 2;
+    let starts = std::iter::once(0).chain(holes.iter().copied());
+    let ends = holes.iter().copied().chain(std::iter::once(n));
+    for (start,end) in starts.zip(ends) {
+        if start >= end || end > n { continue }
+        let ring = &mut positions[start*2..end*2];
+        unwrap_path(ring);
+        let shift = ((reference - ring[0]) / 360.0).round() * 360.0;
+        if shift.is_finite() && shift != 0.0 {
+            for x in ring.iter_mut().step_by(2) { *x += shift }
+        }
+    }
+}
+
+fn unwrap_path(positions: &mut [f32]) {
+    let mut shift = 0.0;
+    let mut prev = match positions.first() {
+        Some(x) => *x,
+        None => return,
+    };
+    for x in positions.iter_mut().step_by(2).skip(1) {
+        let d = *x - prev;
+        prev = *x;
+        if d > 180.0 { shift -= 360.0 }
+        else if d < -180.0 { shift += 360.0 }
+        *x += shift;
+    }
+}
@@ -1,8 +1,10 @@
 use crate::varint;
-use crate::{label, tags};
+use crate::{area_builder, extrude, measure, projection, winding, Projection};
+use crate::triangulate::Triangulation;
+use crate::{label, tags, Encode, Attributes, attributes::EXTENDED};
 use desert::{ToBytesLE, FromBytesLE};
-use earcutr;
 use failure::Error;
+use std::collections::{HashMap,HashSet};
 
 #[test]
 fn peer_area() -> Result<(),Error> {
@@ -12,17 +14,18 @@ fn peer_area() -> Result<(),Error> {
         ("tiger:cfcc", "A41"),
     ];
     let positions: Vec<f32> = vec![
-        31.184799400000003, 29.897739500000004,
-        31.184888100000002, 29.898801400000004,
-        31.184858400000003, 29.8983899,
+        31.1848, 29.89774,
+        31.184889, 29.898802,
+        31.184858, 29.89839,
     ];
     let id: u64 = 234941233;
     let mut area = Area::from_tags(id, &tags)?;
-    area.push(&positions, &vec![]);
+    area.push(&positions, &[]);
 
     let bytes = area.to_bytes_le().unwrap();
+    // the clockwise input ring is written counter-clockwise
     assert_eq!(
-        "03c801b1d6837003787af941922eef41a77af941bf30ef41977af941e72fef410101000200",
+        "03c801b1d6837003787af941922eef41977af941e72fef41a77af941bf30ef410101020000",
         hex::encode(&bytes)
     );
     assert_eq!(
@@ -32,6 +35,141 @@ fn peer_area() -> Result<(),Error> {
     Ok(())
 }
 
+#[test]
+fn area_with_attributes() -> Result<(),Error> {
+    use crate::{decode, decode_ref, Feature, Attributes};
+    let tags = vec![("building", "yes"), ("height", "21 m"), ("building:levels", "6")];
+    let positions: Vec<f32> = vec![0.0, 0.0, 1.0, 0.0, 1.0, 1.0, 0.0, 1.0];
+    let options = tags::Options { attributes: true, ..Default::default() };
+    let mut area = Area::from_tags_with(1, &tags, &options)?;
+    area.push(&positions, &[]);
+    assert_eq!(area.attributes, Attributes {
+        height: Some(21.0), levels: Some(6.0), ..Default::default()
+    });
+    let bytes = area.to_bytes_le()?;
+    assert_eq!(bytes[0], 0x13);
+    let mesh = Area::from_bytes_le(&bytes)?.1.extrude().unwrap();
+    assert_eq!(mesh.len(), 4 + 4*4);
+    assert!(mesh.positions.chunks(3).any(|p| p[2] == 21.0));
+    assert_eq!(decode(&bytes)?, Feature::Area(area.clone()));
+    assert_eq!(decode_ref(&bytes)?.attributes()?, area.attributes);
+    assert_eq!(decode_ref(&bytes)?.into_owned()?, Feature::Area(area));
+    // attributes are opt-in
+    assert_eq!(Area::from_tags(1, &tags)?.to_bytes_le()?[0], 0x03);
+    Ok(())
+}
+
+#[test]
+fn area_rings() {
+    // clockwise outer ring with a clockwise hole
+    let positions: Vec<f32> = vec![
+        0.0, 0.0, 0.0, 4.0, 4.0, 4.0, 4.0, 0.0,
+        1.0, 1.0, 1.0, 2.0, 2.0, 2.0, 2.0, 1.0,
+    ];
+    let mut area = Area::new(1, 0, &[0]);
+    area.push(&positions, &[4]);
+    assert!(winding::is_ccw(&area.positions[0..8]));
+    assert!(!winding::is_ccw(&area.positions[8..16]));
+    let rings = area.rings();
+    assert_eq!(rings.len(), 2);
+    let ring_positions = |ring: &Vec<usize>| {
+        ring.iter().flat_map(|i| vec![area.positions[i*2], area.positions[i*2+1]])
+            .collect::<Vec<f32>>()
+    };
+    let mut areas = rings.iter().map(|r| winding::signed_area(&ring_positions(r)))
+        .collect::<Vec<f64>>();
+    areas.sort_by(|a,b| a.partial_cmp(b).unwrap());
+    assert_eq!(areas, vec![-2.0, 32.0]);
+}
+
+#[test]
+fn area_measures() {
+    // a 0.01 degree square at the equator is about 1.112 km on each side
+    let positions: Vec<f32> = vec![0.0, 0.0, 0.01, 0.0, 0.01, 0.01, 0.0, 0.01];
+    let mut area = Area::new(1, 0, &[0]);
+    area.push(&positions, &[]);
+    let side = measure::distance((0.0, 0.0), (0.01, 0.0));
+    assert!((area.area_m2() - side * side).abs() / (side * side) < 1e-4);
+    let (x,y) = area.centroid().unwrap();
+    assert!((x - 0.005).abs() < 1e-6 && (y - 0.005).abs() < 1e-6);
+    let (x,y) = area.pole_of_inaccessibility(1e-5).unwrap();
+    assert!((x - 0.005).abs() < 1e-4 && (y - 0.005).abs() < 1e-4);
+}
+
+#[test]
+fn area_anchor() -> Result<(),Error> {
+    use crate::decode_ref;
+    // a U shape whose centroid falls in the gap between its arms
+    let positions: Vec<f32> = vec![
+        0.0, 0.0, 3.0, 0.0, 3.0, 3.0, 2.0, 3.0, 2.0, 1.0, 1.0, 1.0, 1.0, 3.0, 0.0, 3.0,
+    ];
+    let mut area = Area::new(1, 0, &[0]);
+    area.push(&positions, &[]);
+    let (cx,cy) = area.centroid().unwrap();
+    assert!(cx > 1.0 && cx < 2.0 && cy > 1.0);
+    let (x,y) = area.anchor().unwrap();
+    assert!(!(1.0..=2.0).contains(&x) || y < 1.0, "{} {}", x, y);
+    area.set_anchor();
+    let bytes = area.to_bytes_le()?;
+    assert_eq!(decode_ref(&bytes)?.anchor()?, Some((x,y)));
+
+    // the pole of a right triangle is its incenter, found to within a
+    // hundredth of the 10-wide bounding box
+    let mut area = Area::new(1, 0, &[0]);
+    area.push(&[0.0, 0.0, 10.0, 0.0, 0.0, 10.0], &[]);
+    let (x,y) = area.anchor().unwrap();
+    let d = x.min(y).min((10.0 - x - y) / 2f32.sqrt()) as f64;
+    let r = (20.0 - 200f64.sqrt()) / 2.0;
+    assert!(d > r - 0.1 && d <= r + 1e-4, "{} {} {}", x, y, d);
+    Ok(())
+}
+
+#[test]
+fn mercator_area() -> Result<(),Error> {
+    let positions: Vec<f32> = vec![0.0, 0.0, 0.01, 0.0, 0.01, 0.01, 0.0, 0.01];
+    let mut lonlat = Area::new(1, 0, &[0]);
+    lonlat.push(&positions, &[]);
+    let options = tags::Options { projection: Projection::Mercator, ..Default::default() };
+    let mut area = Area::from_tags_with(1, &[("leisure", "park")], &options)?;
+    area.push(&positions, &[]);
+    assert_eq!(area.cells, lonlat.cells);
+    assert_eq!(area.positions[0..2], [0.5, 0.5]);
+    assert!((area.area_m2() - lonlat.area_m2()).abs() / lonlat.area_m2() < 1e-3);
+    let bytes = area.to_bytes_le()?;
+    assert_eq!(Area::from_bytes_le(&bytes)?, (bytes.len(),area.clone()));
+    area.project(Projection::LonLat);
+    for (a,b) in area.positions.iter().zip(lonlat.positions.iter()) {
+        assert!((a - b).abs() < 1e-6);
+    }
+    Ok(())
+}
+
+#[test]
+fn area_fallback() -> Result<(),Error> {
+    // a figure eight, as sometimes drawn by mistake
+    let positions: Vec<f32> = vec![0.0, 0.0, 2.0, 2.0, 2.0, 0.0, 0.0, 2.0];
+    let mut area = Area::new(1, 0, &[0]);
+    match area.push_checked(&positions, &[])? {
+        Triangulation::Fallback { .. } => {},
+        x => panic!["expected fallback, got {:?}", x],
+    }
+    assert_eq!(crate::triangulate::mesh_area(&area.positions, &area.cells), 2.0);
+    let mut area = Area::new(1, 0, &[0]);
+    let square: Vec<f32> = vec![0.0, 0.0, 1.0, 0.0, 1.0, 1.0, 0.0, 1.0];
+    assert_eq!(area.push_checked(&square, &[])?, Triangulation::Earcut);
+    // a ring with no area needs no triangles
+    assert_eq!(area.push_checked(&[0.0, 0.0, 1.0, 1.0, 2.0, 2.0], &[])?, Triangulation::Earcut);
+    assert_eq!(area.cells.len(), 6);
+    // push still adds earcut's triangles when the fallback fails
+    let broken: Vec<f32> = vec![0.0, 0.0, 2.0, 2.0, 2.0, f32::NAN, 0.0, 2.0];
+    let mut area = Area::new(1, 0, &[0]);
+    assert!(area.push_checked(&broken, &[]).is_err());
+    assert!(area.positions.is_empty());
+    area.push(&broken, &[]);
+    assert_eq!(area.positions.len(), 8);
+    Ok(())
+}
+
 #[derive(Debug,Clone,PartialEq)]
 pub struct Area {
     pub id: u64,
@@ -39,12 +177,31 @@ pub struct Area {
     pub labels: Vec<u8>,
     pub positions: Vec<f32>,
     pub cells: Vec<usize>,
+    pub attributes: Attributes,
 }
 
 impl Area {
     pub fn from_tags(id: u64, tags: &[(&str, &str)]) -> Result<Area,Error> {
         let (feature_type, labels) = tags::parse(tags)?;
-        Ok(Self { id, feature_type, labels, positions: vec![], cells: vec![] })
+        Ok(Self {
+            id,
+            feature_type,
+            labels,
+            positions: vec![],
+            cells: vec![],
+            attributes: Attributes::default(),
+        })
+    }
+    pub fn from_tags_with(id: u64, tags: &[(&str, &str)], options: &tags::Options) -> Result<Area,Error> {
+        let (feature_type, labels) = tags::parse_with(tags, options)?;
+        Ok(Self {
+            id,
+            feature_type,
+            labels,
+            positions: vec![],
+            cells: vec![],
+            attributes: options.attributes(tags),
+        })
     }
     pub fn new(id: u64, feature_type: u64, labels: &[u8]) -> Area {
         Self {
@@ -52,44 +209,145 @@ impl Area {
             feature_type,
             labels: labels.to_vec(),
             positions: vec![],
-            cells: vec![]
+            cells: vec![],
+            attributes: Attributes::default(),
+        }
+    }
+    /// Triangulate a polygon and add it to the area. `holes` holds the
+    /// position index that each inner ring starts at. Rings are oriented with
+    /// the outer ring counter-clockwise and holes clockwise before
+    /// triangulating, whatever order they arrive in.
+    ///
+    /// `positions` are always lon/lat and are converted to the area's
+    /// projection after triangulating, so winding is in lon/lat terms. Rings
+    /// crossing the antimeridian are unwrapped first (see
+    /// `antimeridian::unwrap`) so they don't span the whole globe.
+    ///
+    /// Broken rings that earcut can't fill are triangulated with
+    /// `triangulate::scanline` instead; use `push_checked` to find out when
+    /// that happens. If that fails too, earcut's triangles are added as they
+    /// are.
+    pub fn push(&mut self, positions: &[f32], holes: &[usize]) {
+        area_builder::with_builder(|builder| builder.push_lenient(self, positions, holes))
+    }
+
+    /// Like `push`, reporting whether the fallback triangulation was needed.
+    /// Fails without adding anything if no triangulation covers the rings.
+    pub fn push_checked(&mut self, positions: &[f32], holes: &[usize]) -> Result<Triangulation,Error> {
+        area_builder::with_builder(|builder| builder.push(self, positions, holes))
+    }
+
+    /// Reconstruct the boundary rings of the triangle mesh as lists of
+    /// position indexes. Outer rings are counter-clockwise and holes are
+    /// clockwise, so the sign of `winding::signed_area` tells them apart.
+    pub fn rings(&self) -> Vec<Vec<usize>> {
+        let p = &self.positions;
+        let mut edges: HashMap<usize,Vec<usize>> = HashMap::new();
+        let mut seen: HashSet<(usize,usize)> = HashSet::new();
+        for c in self.cells.chunks(3) {
+            if c.len() < 3 || c.iter().any(|i| i*2+1 >= p.len()) { continue }
+            let tri = [
+                p[c[0]*2], p[c[0]*2+1], p[c[1]*2], p[c[1]*2+1], p[c[2]*2], p[c[2]*2+1],
+            ];
+            let (a,b,d) = if winding::signed_area(&tri) < 0.0 {
+                (c[0],c[2],c[1])
+            } else {
+                (c[0],c[1],c[2])
+            };
+            for (i,j) in [(a,b),(b,d),(d,a)].iter() {
+                seen.insert((*i,*j));
+            }
+        }
+        // boundary edges are the ones not shared with a neighboring triangle
+        for (i,j) in seen.iter() {
+            if !seen.contains(&(*j,*i)) {
+                edges.entry(*i).or_default().push(*j);
+            }
         }
+        let mut starts = edges.keys().copied().collect::<Vec<usize>>();
+        starts.sort();
+        let mut rings = vec![];
+        for start in starts {
+            while let Some(mut next) = edges.get_mut(&start).and_then(|e| e.pop()) {
+                let mut ring = vec![start];
+                while next != start {
+                    ring.push(next);
+                    match edges.get_mut(&next).and_then(|e| e.pop()) {
+                        Some(n) => next = n,
+                        None => break,
+                    }
+                }
+                rings.push(ring);
+            }
+        }
+        rings
+    }
+
+    /// Area of the triangle mesh on the sphere in square meters.
+    pub fn area_m2(&self) -> f64 {
+        let positions = projection::lonlat(&self.positions, self.attributes.projection);
+        measure::mesh_area(&positions, &self.cells)
+    }
+    /// Area-weighted centroid in the area's own coordinates. May fall outside
+    /// concave areas.
+    pub fn centroid(&self) -> Option<(f32,f32)> {
+        measure::centroid(&self.positions, &self.cells)
+    }
+    /// The point inside the area farthest from its edges, a good place for a
+    /// label. `precision` is in the units of `positions`.
+    pub fn pole_of_inaccessibility(&self, precision: f64) -> Option<(f32,f32)> {
+        measure::pole_of_inaccessibility(&self.positions, &self.rings(), precision)
+    }
+    /// Where to place the area's label: the pole of inaccessibility to within
+    /// a hundredth of the shorter side of the area's bounding box, or the
+    /// centroid if there is no ring.
+    pub fn anchor(&self) -> Option<(f32,f32)> {
+        self.pole_of_inaccessibility(0.0).or_else(|| self.centroid())
+    }
+    /// Store `anchor()` in the attribute section so clients can place the
+    /// label without decoding the mesh.
+    pub fn set_anchor(&mut self) {
+        self.attributes.anchor = self.anchor();
+    }
+    /// Extrude the area into a 3D mesh using the heights in its attributes,
+    /// if it has any. See `extrude::extrude`.
+    pub fn extrude(&self) -> Option<extrude::Mesh> {
+        let (min_height, height) = extrude::heights(&self.attributes)?;
+        Some(extrude::extrude(self, min_height, height))
     }
-    pub fn push(&mut self, positions: &[f32], holes: &[usize]) -> () {
-        let cells = earcutr::earcut(
-            &positions.iter().map(|p| *p as f64).collect(),
-            &holes.to_vec(),
-            2
-        );
-        let offset = self.positions.len() / 2;
-        self.cells.extend(cells.iter().map(|c| c+offset).collect::<Vec<usize>>());
-        self.positions.extend_from_slice(positions);
+    /// Convert the positions and anchor to `to` and record it in the
+    /// attribute section.
+    pub fn project(&mut self, to: Projection) {
+        let from = self.attributes.projection;
+        projection::project(&mut self.positions, from, to);
+        self.attributes.anchor = self.attributes.anchor
+            .map(|p| projection::convert(p, from, to));
+        self.attributes.projection = to;
     }
 }
 
-impl ToBytesLE for Area {
-    fn to_bytes_le(&self) -> Result<Vec<u8>, Error> {
+impl Encode for Area {
+    fn encoded_len(&self) -> usize {
         let pcount = self.positions.len()/2;
-        let ft_length = varint::length(self.feature_type);
-        let id_length = varint::length(self.id);
-        let pcount_length = varint::length(pcount as u64);
-        let clen = varint::length((self.cells.len() / 3) as u64);
         let clen_data = self.cells.iter()
             .fold(0, |acc, c| acc + varint::length(*c as u64));
-
-        let mut buf = vec![
-            0u8;
-            1 + ft_length
-                + id_length
-                + pcount_length
-                + (2 * 4 * pcount)
-                + clen
-                + clen_data
-                + self.labels.len()
-        ];
+        1 + varint::length(self.feature_type)
+            + varint::length(self.id)
+            + varint::length(pcount as u64)
+            + (2 * 4 * pcount)
+            + varint::length((self.cells.len() / 3) as u64)
+            + clen_data
+            + self.labels.len()
+            + if self.attributes.is_empty() { 0 } else { self.attributes.encoded_len() }
+    }
+    fn write_to(&self, buf: &mut [u8]) -> Result<usize, Error> {
+        if buf.len() < self.encoded_len() {
+            failure::bail!["buffer is too small to write area"];
+        }
+        let pcount = self.positions.len()/2;
 
         let mut offset = 0;
-        buf[offset] = 0x03;
+        buf[offset] = if self.attributes.is_empty() { 0x03 } else { 0x03 | EXTENDED };
 
         offset += 1;
         offset += varint::encode(self.feature_type, &mut buf[offset..])?;
@@ -108,14 +366,29 @@ impl ToBytesLE for Area {
             offset += varint::encode(cell as u64, &mut buf[offset..])?;
         }
 
-        buf[offset..].copy_from_slice(&self.labels);
-        return Ok(buf);
+        buf[offset..offset+self.labels.len()].copy_from_slice(&self.labels);
+        offset += self.labels.len();
+        if !self.attributes.is_empty() {
+            offset += self.attributes.write_to(&mut buf[offset..])?;
+        }
+        Ok(offset)
+    }
+}
+
+impl ToBytesLE for Area {
+    fn to_bytes_le(&self) -> Result<Vec<u8>, Error> {
+        let mut buf = vec![0u8; self.encoded_len()];
+        self.write_to(&mut buf)?;
+        Ok(buf)
+    }
+    fn write_bytes_le(&self, dst: &mut [u8]) -> Result<usize, Error> {
+        self.write_to(dst)
     }
 }
 
 impl FromBytesLE for Area {
     fn from_bytes_le(buf: &[u8]) -> Result<(usize,Self), Error> {
-        if buf[0] != 0x03 {
+        if buf[0] & !EXTENDED != 0x03 {
             failure::bail!["parsing line failed. expected 0x03, received 0x{:02x}", buf[0]];
         }
         let mut offset = 1;
@@ -126,6 +399,10 @@ impl FromBytesLE for Area {
 
         let (s,pcount) = varint::decode(&buf[offset..])?;
         offset += s;
+        // check the count against the bytes left before allocating for it
+        if (pcount as usize).checked_mul(8).filter(|n| *n <= buf.len() - offset).is_none() {
+            failure::bail!["not enough bytes to decode {} positions", pcount];
+        }
         let mut positions = Vec::with_capacity((pcount as usize)*2);
         for _ in 0..pcount*2 {
             let (s,x) = f32::from_bytes_le(&buf[offset..])?;
@@ -135,6 +412,10 @@ impl FromBytesLE for Area {
 
         let (s,ccount) = varint::decode(&buf[offset..])?;
         offset += s;
+        // each index takes at least one byte
+        if (ccount as usize).checked_mul(3).filter(|n| *n <= buf.len() - offset).is_none() {
+            failure::bail!["not enough bytes to decode {} cells", ccount];
+        }
         let mut cells = Vec::with_capacity((ccount as usize)*3);
         for _ in 0..ccount*3 {
             let (s,x) = varint::decode(&buf[offset..])?;
@@ -145,6 +426,12 @@ impl FromBytesLE for Area {
         let s = label::scan(&buf[offset..])?;
         let labels = buf[offset..offset+s].to_vec();
         offset += s;
-        Ok((offset, Self { id, positions, cells, feature_type, labels }))
+        let mut attributes = Attributes::default();
+        if buf[0] & EXTENDED != 0 {
+            let (s,attrs) = Attributes::from_bytes_le(&buf[offset..])?;
+            offset += s;
+            attributes = attrs;
+        }
+        Ok((offset, Self { id, positions, cells, feature_type, labels, attributes }))
     }
 }
@@ -0,0 +1,114 @@
+use crate::{antimeridian, projection, triangulate, winding, Area, Projection};
+use crate::triangulate::Triangulation;
+use failure::Error;
+use std::cell::RefCell;
+
+#[test]
+fn builder_matches_push() -> Result<(),Error> {
+    let outer: Vec<f32> = vec![1.3, 1.2, 1.3, 0.3, -0.1, 0.3, -0.1, 1.2, 0.8, 0.7, 0.5, 0.5, 1.0, 0.6];
+    let second: Vec<f64> = vec![179.0, 0.0, -179.0, 0.0, -179.0, 1.0, 179.0, 1.0];
+    let mut expected = Area::new(1, 0, &[0]);
+    expected.push(&outer, &[4]);
+    expected.push(&second.iter().map(|p| *p as f32).collect::<Vec<f32>>(), &[]);
+
+    let mut builder = AreaBuilder::new();
+    let mut area = Area::new(1, 0, &[0]);
+    builder.push(&mut area, &outer, &[4])?;
+    builder.push_f64(&mut area, &second, &[])?;
+    assert_eq!(area, expected);
+    Ok(())
+}
+
+thread_local! {
+    static BUILDER: RefCell<AreaBuilder> = RefCell::new(AreaBuilder::new());
+}
+
+/// Run `f` with this thread's builder, which `Area::push` and the `encode`
+/// functions share so their buffers outlive a single call.
+pub(crate) fn with_builder<T, F: FnOnce(&mut AreaBuilder) -> T>(f: F) -> T {
+    BUILDER.with(|builder| f(&mut builder.borrow_mut()))
+}
+
+/// Triangulates rings into `Area`s like `Area::push`, reusing its buffers
+/// from one call to the next. Keep one around when encoding many areas.
+#[derive(Debug,Clone,Default)]
+pub struct AreaBuilder {
+    positions: Vec<f32>,
+    coords: Vec<f64>,
+    holes: Vec<usize>,
+}
+
+impl AreaBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Triangulate a polygon and add it to `area`, as `Area::push_checked`.
+    pub fn push(
+        &mut self, area: &mut Area, positions: &[f32], holes: &[usize]
+    ) -> Result<Triangulation,Error> {
+        self.positions.clear();
+        self.positions.extend_from_slice(positions);
+        self.triangulate(area, holes, true)
+    }
+
+    /// Like `push`, but if no triangulation covers the rings, earcut's
+    /// triangles are added anyway, as `Area::push` always did.
+    pub(crate) fn push_lenient(&mut self, area: &mut Area, positions: &[f32], holes: &[usize]) {
+        self.positions.clear();
+        self.positions.extend_from_slice(positions);
+        let _ = self.triangulate(area, holes, false);
+    }
+
+    /// Like `push` with f64 positions, such as those from an OSM reader. They
+    /// are rounded to f32 first, as they are stored, and triangulated from
+    /// there, so the result is the same as pushing the rounded positions: this
+    /// saves the caller a conversion, not precision.
+    pub fn push_f64(
+        &mut self, area: &mut Area, positions: &[f64], holes: &[usize]
+    ) -> Result<Triangulation,Error> {
+        self.positions.clear();
+        self.positions.extend(positions.iter().map(|p| *p as f32));
+        self.triangulate(area, holes, true)
+    }
+
+    fn triangulate(
+        &mut self, area: &mut Area, holes: &[usize], strict: bool
+    ) -> Result<Triangulation,Error> {
+        // rings pushed after the first sit next to the area's first position
+        let reference = match area.positions.get(0..2) {
+            Some(p) => projection::convert((p[0], p[1]), area.attributes.projection, Projection::LonLat).0,
+            None => self.positions.first().copied().unwrap_or(0.0),
+        };
+        antimeridian::unwrap_near(&mut self.positions, holes, reference);
+        winding::normalize(&mut self.positions, holes);
+        self.coords.clear();
+        self.coords.extend(self.positions.iter().map(|p| *p as f64));
+        self.holes.clear();
+        self.holes.extend_from_slice(holes);
+
+        let mut cells = earcutr::earcut(&self.coords, &self.holes, 2);
+        let coverage = triangulate::coverage(&self.positions, holes, &cells);
+        let mut method = Triangulation::Earcut;
+        // a NaN coverage, from positions that aren't finite, isn't covered
+        let covered = (coverage - 1.0).abs() <= triangulate::TOLERANCE;
+        if !covered {
+            match triangulate::scanline(&self.positions, holes) {
+                Ok((points, scanned)) if !scanned.is_empty() => {
+                    self.positions.clear();
+                    self.positions.extend_from_slice(&points);
+                    cells = scanned;
+                    method = Triangulation::Fallback { coverage };
+                },
+                Err(e) if strict => failure::bail!["failed to triangulate area {}: {}", area.id, e],
+                _ if strict => failure::bail!["failed to triangulate area {}", area.id],
+                _ => {},
+            }
+        }
+        projection::project(&mut self.positions, Projection::LonLat, area.attributes.projection);
+        let offset = area.positions.len() / 2;
+        area.cells.extend(cells.iter().map(|c| c+offset));
+        area.positions.extend_from_slice(&self.positions);
+        Ok(method)
+    }
+}
@@ -0,0 +1,415 @@
+use crate::varint;
+use crate::dictionary::{Dictionary, TagRef};
+use crate::projection::Projection;
+use desert::{ToBytesLE,FromBytesLE};
+use failure::Error;
+
+#[test]
+fn parse_attributes() {
+    let attrs = Attributes::from_tags(&[
+        ("building", "yes"), ("height", "12 m"), ("min_height", "3.5m"),
+        ("building:levels", "4"), ("layer", "-1"), ("width", "12'6\""), ("lanes", "2;3"),
+    ]);
+    assert_eq!(attrs, Attributes {
+        layer: Some(-1),
+        height: Some(12.0),
+        min_height: Some(3.5),
+        levels: Some(4.0),
+        width: Some(3.81),
+        lanes: Some(2),
+        tags: vec![],
+        flags: 0,
+        anchor: None,
+        projection: Projection::LonLat,
+    });
+    assert_eq!(parse_length("40 ft"), Some(12.192));
+    assert_eq!(parse_length("3,5"), Some(3.5));
+    assert_eq!(parse_length("1.2 km"), Some(1200.0));
+    assert_eq!(parse_length("tall"), None);
+    assert!(Attributes::from_tags(&[("layer", "x"), ("name", "y")]).is_empty());
+}
+
+#[test]
+fn attributes_round_trip() -> Result<(),Error> {
+    let attrs = Attributes {
+        layer: Some(-2), height: Some(8.5), lanes: Some(4), anchor: Some((1.5, -2.25)),
+        projection: Projection::Mercator,
+        ..Default::default()
+    };
+    let mut buf = vec![0u8; attrs.encoded_len()];
+    assert_eq!(attrs.write_to(&mut buf)?, buf.len());
+    assert_eq!(Attributes::from_bytes_le(&buf)?, (buf.len(), attrs));
+    Ok(())
+}
+
+#[test]
+fn attributes_forward_compatible() -> Result<(),Error> {
+    let attrs = Attributes { layer: Some(1), lanes: Some(2), ..Default::default() };
+    let mut buf = vec![0u8; attrs.encoded_len()];
+    attrs.write_to(&mut buf)?;
+    // splice in a 3-byte value under a key this version doesn't know
+    let mut newer = vec![3];
+    newer.extend_from_slice(&buf[1..]);
+    newer.extend_from_slice(&[42, 3, 1, 2, 3]);
+    assert_eq!(scan(&newer)?, newer.len());
+    assert_eq!(Attributes::from_bytes_le(&newer)?, (newer.len(), attrs));
+    for n in 0..newer.len() {
+        assert!(scan(&newer[0..n]).is_err());
+        assert!(Attributes::from_bytes_le(&newer[0..n]).is_err());
+    }
+    // a huge tag count
+    let bad = [1, 6, 10, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x7f, 0];
+    assert!(Attributes::from_bytes_le(&bad).is_err());
+    Ok(())
+}
+
+#[test]
+fn dictionary_tags() -> Result<(),Error> {
+    use crate::dictionary::DEFAULT_TAG_KEYS;
+    let dict = Dictionary::builtin();
+    let tags = vec![
+        ("highway", "residential"), ("surface", "asphalt"), ("oneway", "yes"),
+        ("access", "residents_only"),
+    ];
+    let attrs = Attributes {
+        tags: Attributes::select_tags(&tags, &DEFAULT_TAG_KEYS, &dict),
+        ..Default::default()
+    };
+    let mut buf = vec![0u8; attrs.encoded_len()];
+    attrs.write_to(&mut buf)?;
+    // 2 bytes per dictionary pair, plus the inline string
+    assert_eq!(buf.len(), 1 + 1 + 1 + 1 + 2 + 2 + 2 + 15);
+    assert_eq!(scan(&buf)?, buf.len());
+    let (_,decoded) = Attributes::from_bytes_le(&buf)?;
+    assert_eq!(decoded.tags(&dict)?, vec![
+        ("surface", "asphalt"), ("oneway", "yes"), ("access", "residents_only"),
+    ]);
+    assert!(decoded.tags(&Dictionary::new(&["surface"])).is_err());
+    Ok(())
+}
+
+/// Type byte bit set on point, line and area records that carry an
+/// attribute section after their labels.
+pub const EXTENDED: u8 = 0x10;
+
+const LAYER: u64 = 0;
+const HEIGHT: u64 = 1;
+const MIN_HEIGHT: u64 = 2;
+const LEVELS: u64 = 3;
+const WIDTH: u64 = 4;
+const LANES: u64 = 5;
+const TAGS: u64 = 6;
+const FLAGS: u64 = 7;
+const ANCHOR: u64 = 8;
+const PROJECTION: u64 = 9;
+
+/// Numeric properties parsed from tags. Lengths are in meters.
+///
+/// When any attribute is set, a record's type byte has `EXTENDED` set and the
+/// labels are followed by a varint count of attributes, each stored as a
+/// varint key, a varint byte length and a value: a zigzag varint for `layer`,
+/// a varint for `lanes`, a list of key/value pairs for `tags` and a
+/// little-endian f32 otherwise. Readers skip keys they don't know.
+///
+/// `flags` is a varint bitfield, omitted when zero. Lines use it for
+/// `LineFlags`, which `Line` exposes as its `flags` field instead.
+///
+/// `anchor` is a `(lon,lat)` label position stored as two little-endian f32s,
+/// set by `Line::set_anchor` and `Area::set_anchor`.
+///
+/// `projection` says how the record's positions (and anchor) are stored, as a
+/// varint id omitted for the default of lon/lat.
+///
+/// `tags` holds whitelisted tags as references into a `Dictionary`. Each
+/// reference is stored as a varint of the dictionary id plus one, or a zero
+/// followed by a varint length-prefixed string when it is not in the
+/// dictionary.
+#[derive(Debug,Clone,Default,PartialEq)]
+pub struct Attributes {
+    pub layer: Option<i64>,
+    pub height: Option<f32>,
+    pub min_height: Option<f32>,
+    pub levels: Option<f32>,
+    pub width: Option<f32>,
+    pub lanes: Option<u64>,
+    pub tags: Vec<(TagRef, TagRef)>,
+    pub flags: u64,
+    pub anchor: Option<(f32, f32)>,
+    pub projection: Projection,
+}
+
+impl Attributes {
+    /// Parse attributes from `layer`, `height`, `min_height`,
+    /// `building:levels`, `width` and `lanes` tags. Values that can't be
+    /// understood are skipped.
+    pub fn from_tags(tags: &[(&str, &str)]) -> Self {
+        let mut attrs = Self::default();
+        for (k,v) in tags.iter() {
+            match *k {
+                "layer" => attrs.layer = parse_number(v).map(|x| x.round() as i64),
+                "height" => attrs.height = parse_length(v),
+                "min_height" => attrs.min_height = parse_length(v),
+                "building:levels" => attrs.levels = parse_number(v).map(|x| x as f32),
+                "width" => attrs.width = parse_length(v),
+                "lanes" => attrs.lanes = parse_number(v)
+                    .filter(|x| *x >= 0.0)
+                    .map(|x| x.round() as u64),
+                _ => {},
+            }
+        }
+        attrs
+    }
+    /// Look up the tags whose key is in `keys` in `dict`.
+    pub fn select_tags<S: AsRef<str>>(
+        tags: &[(&str, &str)],
+        keys: &[S],
+        dict: &Dictionary,
+    ) -> Vec<(TagRef, TagRef)> {
+        tags.iter()
+            .filter(|(k,_)| keys.iter().any(|key| key.as_ref() == *k))
+            .map(|(k,v)| (dict.tag_ref(k), dict.tag_ref(v)))
+            .collect()
+    }
+    /// Resolve `tags` against the dictionary they were encoded with.
+    pub fn tags<'a>(&'a self, dict: &'a Dictionary) -> Result<Vec<(&'a str, &'a str)>,Error> {
+        self.tags.iter().map(|(k,v)| Ok((dict.resolve(k)?, dict.resolve(v)?))).collect()
+    }
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+    fn count(&self) -> usize {
+        [
+            self.layer.is_some(), self.height.is_some(), self.min_height.is_some(),
+            self.levels.is_some(), self.width.is_some(), self.lanes.is_some(),
+            !self.tags.is_empty(), self.flags != 0, self.anchor.is_some(),
+            self.projection != Projection::LonLat,
+        ].iter().filter(|x| **x).count()
+    }
+    fn tags_length(&self) -> usize {
+        varint::length(self.tags.len() as u64) + self.tags.iter()
+            .map(|(k,v)| tag_ref_length(k) + tag_ref_length(v))
+            .sum::<usize>()
+    }
+    // key and length of every value that is set, in key order
+    fn entries(&self) -> Vec<(u64,usize)> {
+        let mut entries = vec![];
+        if let Some(x) = self.layer { entries.push((LAYER, varint::length(zigzag(x)))) }
+        if self.height.is_some() { entries.push((HEIGHT, 4)) }
+        if self.min_height.is_some() { entries.push((MIN_HEIGHT, 4)) }
+        if self.levels.is_some() { entries.push((LEVELS, 4)) }
+        if self.width.is_some() { entries.push((WIDTH, 4)) }
+        if let Some(x) = self.lanes { entries.push((LANES, varint::length(x))) }
+        if !self.tags.is_empty() { entries.push((TAGS, self.tags_length())) }
+        if self.flags != 0 { entries.push((FLAGS, varint::length(self.flags))) }
+        if self.anchor.is_some() { entries.push((ANCHOR, 8)) }
+        if self.projection != Projection::LonLat {
+            entries.push((PROJECTION, varint::length(self.projection.id())));
+        }
+        entries
+    }
+    pub fn encoded_len(&self) -> usize {
+        varint::length(self.count() as u64) + self.entries().iter()
+            .map(|(key,len)| varint::length(*key) + varint::length(*len as u64) + len)
+            .sum::<usize>()
+    }
+    pub fn write_to(&self, buf: &mut [u8]) -> Result<usize,Error> {
+        if buf.len() < self.encoded_len() {
+            failure::bail!["buffer is too small to write attributes"];
+        }
+        let mut offset = varint::encode(self.count() as u64, buf)?;
+        for (key,len) in self.entries() {
+            offset += varint::encode(key, &mut buf[offset..])?;
+            offset += varint::encode(len as u64, &mut buf[offset..])?;
+            let value = &mut buf[offset..offset+len];
+            match key {
+                LAYER => { varint::encode(zigzag(self.layer.unwrap_or(0)), value)?; },
+                HEIGHT => { self.height.unwrap_or(0.0).write_bytes_le(value)?; },
+                MIN_HEIGHT => { self.min_height.unwrap_or(0.0).write_bytes_le(value)?; },
+                LEVELS => { self.levels.unwrap_or(0.0).write_bytes_le(value)?; },
+                WIDTH => { self.width.unwrap_or(0.0).write_bytes_le(value)?; },
+                LANES => { varint::encode(self.lanes.unwrap_or(0), value)?; },
+                TAGS => {
+                    let mut o = varint::encode(self.tags.len() as u64, value)?;
+                    for (k,v) in self.tags.iter() {
+                        o += write_tag_ref(k, &mut value[o..])?;
+                        o += write_tag_ref(v, &mut value[o..])?;
+                    }
+                },
+                FLAGS => { varint::encode(self.flags, value)?; },
+                ANCHOR => {
+                    let (x,y) = self.anchor.unwrap_or((0.0,0.0));
+                    x.write_bytes_le(&mut value[0..4])?;
+                    y.write_bytes_le(&mut value[4..8])?;
+                },
+                _ => { varint::encode(self.projection.id(), value)?; },
+            }
+            offset += len;
+        }
+        Ok(offset)
+    }
+}
+
+impl FromBytesLE for Attributes {
+    fn from_bytes_le(buf: &[u8]) -> Result<(usize,Self),Error> {
+        let mut attrs = Self::default();
+        let mut offset = varint::decode(buf)?.0;
+        for entry in Entries::new(buf)? {
+            let (end,key,value) = entry?;
+            offset = end;
+            match key {
+                LAYER => attrs.layer = Some(unzigzag(varint::decode(value)?.1)),
+                LANES => attrs.lanes = Some(varint::decode(value)?.1),
+                FLAGS => attrs.flags = varint::decode(value)?.1,
+                PROJECTION => attrs.projection = Projection::from_id(varint::decode(value)?.1)?,
+                HEIGHT => attrs.height = Some(f32::from_bytes_le(value)?.1),
+                MIN_HEIGHT => attrs.min_height = Some(f32::from_bytes_le(value)?.1),
+                LEVELS => attrs.levels = Some(f32::from_bytes_le(value)?.1),
+                WIDTH => attrs.width = Some(f32::from_bytes_le(value)?.1),
+                TAGS => {
+                    let (mut o,n) = varint::decode(value)?;
+                    // each tag takes at least two bytes
+                    if n > (value.len() / 2) as u64 {
+                        failure::bail!["not enough bytes to decode {} tags", n];
+                    }
+                    for _ in 0..n {
+                        let (s,k) = read_tag_ref(&value[o..])?;
+                        o += s;
+                        let (s,v) = read_tag_ref(&value[o..])?;
+                        o += s;
+                        attrs.tags.push((k,v));
+                    }
+                },
+                ANCHOR => {
+                    let (s,x) = f32::from_bytes_le(value)?;
+                    let (_,y) = f32::from_bytes_le(&value[s..])?;
+                    attrs.anchor = Some((x,y));
+                },
+                // written by a newer version
+                _ => {},
+            }
+        }
+        Ok((offset,attrs))
+    }
+}
+
+// Iterates over `(end offset, key, value)` of each attribute, checking every
+// value lies within the buffer.
+struct Entries<'a> {
+    buf: &'a [u8],
+    offset: usize,
+    remaining: u64,
+}
+
+impl<'a> Entries<'a> {
+    fn new(buf: &'a [u8]) -> Result<Self,Error> {
+        let (offset,count) = varint::decode(buf)?;
+        Ok(Self { buf, offset, remaining: count })
+    }
+    fn read(&mut self) -> Result<(usize,u64,&'a [u8]),Error> {
+        let (s,key) = varint::decode(&self.buf[self.offset..])?;
+        self.offset += s;
+        let (s,len) = varint::decode(&self.buf[self.offset..])?;
+        self.offset += s;
+        let start = self.offset;
+        self.offset = (len as usize).checked_add(start)
+            .filter(|end| *end <= self.buf.len())
+            .ok_or_else(|| failure::format_err!["not enough bytes to decode attributes"])?;
+        Ok((self.offset,key,&self.buf[start..self.offset]))
+    }
+}
+
+impl<'a> Iterator for Entries<'a> {
+    type Item = Result<(usize,u64,&'a [u8]),Error>;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 { return None }
+        self.remaining -= 1;
+        let entry = self.read();
+        if entry.is_err() { self.remaining = 0 }
+        Some(entry)
+    }
+}
+
+/// Length in bytes of the attribute section at the start of `buf`.
+pub fn scan(buf: &[u8]) -> Result<usize,Error> {
+    let mut offset = varint::decode(buf)?.0;
+    for entry in Entries::new(buf)? {
+        offset = entry?.0;
+    }
+    Ok(offset)
+}
+
+fn tag_ref_length(r: &TagRef) -> usize {
+    match r {
+        TagRef::Id(id) => varint::length(id + 1),
+        TagRef::Str(s) => 1 + varint::length(s.len() as u64) + s.len(),
+    }
+}
+
+fn write_tag_ref(r: &TagRef, buf: &mut [u8]) -> Result<usize,Error> {
+    match r {
+        TagRef::Id(id) => varint::encode(id + 1, buf),
+        TagRef::Str(s) => {
+            let mut offset = varint::encode(0, buf)?;
+            offset += varint::encode(s.len() as u64, &mut buf[offset..])?;
+            buf[offset..offset+s.len()].copy_from_slice(s.as_bytes());
+            Ok(offset + s.len())
+        },
+    }
+}
+
+fn read_tag_ref(buf: &[u8]) -> Result<(usize,TagRef),Error> {
+    let (s,x) = varint::decode(buf)?;
+    if x > 0 { return Ok((s,TagRef::Id(x-1))) }
+    let (t,len) = varint::decode(&buf[s..])?;
+    let start = s + t;
+    let end = start + (len as usize);
+    if end > buf.len() { failure::bail!["not enough bytes to decode tag"] }
+    Ok((end,TagRef::Str(String::from_utf8(buf[start..end].to_vec())?)))
+}
+
+fn zigzag(x: i64) -> u64 {
+    ((x << 1) ^ (x >> 63)) as u64
+}
+
+fn unzigzag(x: u64) -> i64 {
+    ((x >> 1) as i64) ^ -((x & 1) as i64)
+}
+
+/// Parse the leading number of a tag value, accepting a decimal comma and
+/// ignoring anything after it such as units or further `;` values.
+pub fn parse_number(value: &str) -> Option<f64> {
+    let value = value.trim();
+    let end = value.char_indices()
+        .find(|(i,c)| !(c.is_ascii_digit() || *c == '.' || *c == ','
+            || (*i == 0 && (*c == '-' || *c == '+'))))
+        .map(|(i,_)| i)
+        .unwrap_or(value.len());
+    value[..end].replace(',', ".").parse::<f64>().ok()
+}
+
+/// Parse a length such as `12`, `12 m`, `1.2 km`, `40 ft` or `12'6"` into
+/// meters.
+pub fn parse_length(value: &str) -> Option<f32> {
+    let value = value.trim();
+    if let Some(i) = value.find('\'') {
+        // feet and inches
+        let feet = parse_number(&value[..i])?;
+        let inches = parse_number(value[i+1..].trim_end_matches('"')).unwrap_or(0.0);
+        return Some(((feet * 12.0 + inches) * 0.0254) as f32);
+    }
+    let x = parse_number(value)?;
+    let unit = value.trim_start_matches(|c: char| {
+        c.is_ascii_digit() || c == '.' || c == ',' || c == '-' || c == '+'
+    }).trim();
+    let scale = match unit {
+        "" | "m" => 1.0,
+        "km" => 1000.0,
+        "cm" => 0.01,
+        "mm" => 0.001,
+        "ft" | "feet" => 0.3048,
+        "mi" => 1609.344,
+        "in" | "\"" => 0.0254,
+        _ => return None,
+    };
+    Some((x * scale) as f32)
+}
@@ -0,0 +1,289 @@
+use crate::{encode, tags, Member};
+use crate::triangulate::Triangulation;
+use failure::Error;
+use std::collections::HashMap;
+use std::thread;
+
+#[test]
+fn batch_matches_sequential() -> Result<(),Error> {
+    use crate::MemberRole;
+    use crate::MemberType;
+    let mut nodes = HashMap::new();
+    nodes.insert(100, (1.3, 1.2));
+    nodes.insert(101, (1.3, 0.3));
+    nodes.insert(102, (-0.1, 0.3));
+    nodes.insert(103, (-0.1, 1.2));
+    let mut ways = HashMap::new();
+    ways.insert(200, vec![100,101,102,103,100]);
+    let elements = vec![
+        Element::Node { id: 100, point: (1.3, 1.2), tags: vec![("amenity","cafe")] },
+        Element::Way { id: 200, tags: vec![("leisure","park")], refs: vec![100,101,102,103,100] },
+        Element::Way { id: 201, tags: vec![("highway","residential")], refs: vec![100,101] },
+        Element::Way { id: 202, tags: vec![], refs: vec![100] },
+        Element::Relation {
+            id: 300,
+            tags: vec![("type","multipolygon"),("natural","water")],
+            members: vec![Member::new(200, MemberRole::Outer(), MemberType::Way())],
+        },
+    ];
+    let expected = vec![
+        encode::node(100, (1.3, 1.2), &[("amenity","cafe")])?,
+        encode::way(200, &[("leisure","park")], &[100,101,102,103,100], &nodes)?,
+        encode::way(201, &[("highway","residential")], &[100,101], &nodes)?,
+        vec![],
+        encode::relation(300, &[("type","multipolygon"),("natural","water")],
+            &[Member::new(200, MemberRole::Outer(), MemberType::Way())], &nodes, &ways)?,
+    ];
+    for threads in 1..=6 {
+        let batch = Batch::new(&nodes, &ways).with_threads(threads);
+        let (buffers, skipped, fallbacks) = batch.encode(&elements)?;
+        assert_eq!(buffers, expected);
+        assert!(skipped.is_empty() && fallbacks.is_empty());
+        let mut buf = vec![0xff];
+        let (n, skipped, fallbacks) = batch.encode_into(&elements, &mut buf)?;
+        assert_eq!(n, buf.len() - 1);
+        assert!(skipped.is_empty() && fallbacks.is_empty());
+        assert_eq!(buf[1..].to_vec(), expected.concat());
+    }
+    let options = tags::Options { projection: crate::Projection::Mercator, ..Default::default() };
+    let (buffers, _, _) = Batch::new(&nodes, &ways).with_threads(2).with_options(&options).encode(&elements)?;
+    assert_eq!(buffers[0], encode::node_with(100, (1.3, 1.2), &[("amenity","cafe")], &options)?);
+    assert_eq!(buffers[4], encode::relation_with(300, &[("type","multipolygon"),("natural","water")],
+        &[Member::new(200, MemberRole::Outer(), MemberType::Way())], &nodes, &ways, &options)?);
+    Ok(())
+}
+
+#[test]
+fn batch_skips_failed_elements() -> Result<(),Error> {
+    let mut nodes = HashMap::new();
+    nodes.insert(100, (1.3, 1.2));
+    nodes.insert(101, (1.3, 0.3));
+    let ways = HashMap::new();
+    let elements = vec![
+        Element::Way { id: 200, tags: vec![("highway","residential")], refs: vec![100,101] },
+        // node 102 is missing from the extract
+        Element::Way { id: 201, tags: vec![("highway","residential")], refs: vec![100,102] },
+        Element::Node { id: 100, point: (1.3, 1.2), tags: vec![("amenity","cafe")] },
+    ];
+    for threads in 1..=3 {
+        let batch = Batch::new(&nodes, &ways).with_threads(threads);
+        let (buffers, skipped, _) = batch.encode(&elements)?;
+        assert_eq!(buffers.len(), 3);
+        assert!(!buffers[0].is_empty() && buffers[1].is_empty() && !buffers[2].is_empty());
+        assert_eq!(skipped.len(), 1);
+        assert_eq!((skipped[0].index, skipped[0].id), (1, 201));
+        assert!(skipped[0].error.to_string().starts_with("way 201: "));
+        let mut buf = vec![];
+        let (n, skipped, _) = batch.encode_into(&elements, &mut buf)?;
+        assert_eq!(n, buffers[0].len() + buffers[2].len());
+        assert_eq!(skipped.len(), 1);
+    }
+    Ok(())
+}
+
+#[test]
+fn batch_reports_fallbacks() -> Result<(),Error> {
+    let mut nodes = HashMap::new();
+    // a figure eight, as sometimes drawn by mistake
+    nodes.insert(1, (0.0, 0.0));
+    nodes.insert(2, (2.0, 2.0));
+    nodes.insert(3, (2.0, 0.0));
+    nodes.insert(4, (0.0, 2.0));
+    nodes.insert(5, (3.0, 0.0));
+    nodes.insert(6, (4.0, 0.0));
+    nodes.insert(7, (4.0, 1.0));
+    let ways = HashMap::new();
+    let elements = vec![
+        Element::Way { id: 10, tags: vec![("leisure","park")], refs: vec![5,6,7,5] },
+        Element::Way { id: 11, tags: vec![("leisure","park")], refs: vec![1,2,3,4,1] },
+    ];
+    for threads in 1..=2 {
+        let batch = Batch::new(&nodes, &ways).with_threads(threads);
+        let (buffers, skipped, fallbacks) = batch.encode(&elements)?;
+        assert!(skipped.is_empty() && !buffers[1].is_empty());
+        assert_eq!(fallbacks.len(), 1);
+        assert_eq!((fallbacks[0].index, fallbacks[0].id), (1, 11));
+        assert!(fallbacks[0].coverage != 1.0);
+        let (_, _, fallbacks) = batch.encode_into(&elements, &mut vec![])?;
+        assert_eq!(fallbacks.len(), 1);
+    }
+    Ok(())
+}
+
+/// An OSM element to be encoded as part of a `Batch`.
+#[derive(Debug,Clone,PartialEq)]
+pub enum Element<'a> {
+    Node { id: u64, point: (f32, f32), tags: Vec<(&'a str, &'a str)> },
+    Way { id: u64, tags: Vec<(&'a str, &'a str)>, refs: Vec<u64> },
+    Relation { id: u64, tags: Vec<(&'a str, &'a str)>, members: Vec<Member> },
+}
+
+impl<'a> Element<'a> {
+    pub fn id(&self) -> u64 {
+        match self {
+            Self::Node { id, .. } | Self::Way { id, .. } | Self::Relation { id, .. } => *id,
+        }
+    }
+    fn kind(&self) -> &'static str {
+        match self {
+            Self::Node { .. } => "node",
+            Self::Way { .. } => "way",
+            Self::Relation { .. } => "relation",
+        }
+    }
+}
+
+/// Encodes chunks of elements across threads against shared, read-only node
+/// and way stores. Output is always in input order regardless of how many
+/// threads are used, so repeated runs produce identical bytes.
+#[derive(Debug,Clone)]
+pub struct Batch<'a> {
+    nodes: &'a HashMap<u64, (f32, f32)>,
+    ways: &'a HashMap<u64, Vec<u64>>,
+    options: &'a tags::Options,
+    threads: usize,
+}
+
+impl<'a> Batch<'a> {
+    pub fn new(nodes: &'a HashMap<u64, (f32, f32)>, ways: &'a HashMap<u64, Vec<u64>>) -> Self {
+        let threads = thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+        Self { nodes, ways, options: &tags::DEFAULT_OPTIONS, threads }
+    }
+    pub fn with_threads(mut self, threads: usize) -> Self {
+        self.threads = threads.max(1);
+        self
+    }
+    /// Parse tags with `options`, as the `encode::*_with` functions do.
+    pub fn with_options(mut self, options: &'a tags::Options) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Encode each element into its own buffer. Elements with nothing to
+    /// encode produce an empty buffer, as the `encode` functions do. So do
+    /// elements that fail to encode, such as a way with a node missing from
+    /// a clipped extract; they are also returned as `Skipped`. Areas that
+    /// had to be triangulated with the fallback are returned as `Fallback`.
+    pub fn encode(&self, elements: &[Element]) -> Result<Encoded, Error> {
+        let chunks = self.run(elements)?;
+        let mut out = Vec::with_capacity(elements.len());
+        let (mut skipped, mut fallbacks) = (vec![], vec![]);
+        for chunk in chunks.into_iter() {
+            let mut start = 0;
+            for end in chunk.offsets.iter() {
+                out.push(chunk.buf[start..*end].to_vec());
+                start = *end;
+            }
+            skipped.extend(chunk.skipped);
+            fallbacks.extend(chunk.fallbacks);
+        }
+        Ok((out, skipped, fallbacks))
+    }
+
+    /// Append every encoded element to `buf`, returning the number of bytes
+    /// written, the elements that failed to encode and were left out and the
+    /// areas triangulated with the fallback.
+    pub fn encode_into(
+        &self, elements: &[Element], buf: &mut Vec<u8>
+    ) -> Result<(usize, Vec<Skipped>, Vec<Fallback>), Error> {
+        let chunks = self.run(elements)?;
+        let start = buf.len();
+        buf.reserve(chunks.iter().map(|c| c.buf.len()).sum());
+        let (mut skipped, mut fallbacks) = (vec![], vec![]);
+        for chunk in chunks.into_iter() {
+            buf.extend_from_slice(&chunk.buf);
+            skipped.extend(chunk.skipped);
+            fallbacks.extend(chunk.fallbacks);
+        }
+        Ok((buf.len() - start, skipped, fallbacks))
+    }
+
+    // Each thread encodes one contiguous chunk into a single buffer and
+    // records where each element ends, so chunks can be joined in order.
+    fn run(&self, elements: &[Element]) -> Result<Vec<Chunk>, Error> {
+        if elements.is_empty() { return Ok(vec![]) }
+        let size = elements.len().div_ceil(self.threads);
+        if size == elements.len() {
+            return Ok(vec![self.encode_chunk(0, elements)]);
+        }
+        thread::scope(|scope| {
+            let handles = elements.chunks(size).enumerate()
+                .map(|(i,chunk)| scope.spawn(move || self.encode_chunk(i * size, chunk)))
+                .collect::<Vec<_>>();
+            handles.into_iter().map(|h| {
+                h.join().map_err(|_| format_err!["batch encoding thread panicked"])
+            }).collect()
+        })
+    }
+
+    fn encode_chunk(&self, first: usize, elements: &[Element]) -> Chunk {
+        let mut chunk = Chunk {
+            buf: vec![],
+            offsets: Vec::with_capacity(elements.len()),
+            skipped: vec![],
+            fallbacks: vec![],
+        };
+        for (i, element) in elements.iter().enumerate() {
+            let start = chunk.buf.len();
+            let result = match element {
+                Element::Node { id, point, tags } => {
+                    encode::node_with_into(*id, *point, tags, self.options, &mut chunk.buf)
+                        .map(|n| (n, None))
+                },
+                Element::Way { id, tags, refs } => {
+                    encode::way_with_into(*id, tags, refs, self.nodes, self.options, &mut chunk.buf)
+                },
+                Element::Relation { id, tags, members } => {
+                    let (nodes, ways, options) = (self.nodes, self.ways, self.options);
+                    encode::relation_with_into(*id, tags, members, nodes, ways, options, &mut chunk.buf)
+                },
+            };
+            match result {
+                Ok((_, Some(Triangulation::Fallback { coverage }))) => {
+                    chunk.fallbacks.push(Fallback { index: first + i, id: element.id(), coverage });
+                },
+                Ok(_) => {},
+                Err(error) => {
+                    chunk.buf.truncate(start);
+                    chunk.skipped.push(Skipped {
+                        index: first + i,
+                        id: element.id(),
+                        error: format_err!["{} {}: {}", element.kind(), element.id(), error],
+                    });
+                },
+            }
+            chunk.offsets.push(chunk.buf.len());
+        }
+        chunk
+    }
+}
+
+struct Chunk {
+    buf: Vec<u8>,
+    offsets: Vec<usize>,
+    skipped: Vec<Skipped>,
+    fallbacks: Vec<Fallback>,
+}
+
+/// Encoded records along with the elements that were skipped and the areas
+/// that fell back, as returned by `Batch::encode` and `osm_xml::encode_str`.
+pub type Encoded = (Vec<Vec<u8>>, Vec<Skipped>, Vec<Fallback>);
+
+/// An element that failed to encode and was left out of a batch.
+#[derive(Debug)]
+pub struct Skipped {
+    /// position of the element in the input
+    pub index: usize,
+    pub id: u64,
+    pub error: Error,
+}
+
+/// An area whose rings earcut couldn't fill, so it was encoded with
+/// `triangulate::scanline` instead. See `Triangulation::Fallback`.
+#[derive(Debug,Clone,PartialEq)]
+pub struct Fallback {
+    /// position of the element in the input
+    pub index: usize,
+    pub id: u64,
+    pub coverage: f64,
+}
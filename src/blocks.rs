@@ -0,0 +1,384 @@
+use crate::container::{self, Compression, Header, Records};
+use crate::{decode_ref, varint, Encode, Feature, FeatureRef, Projection};
+use desert::FromBytesLE;
+use failure::Error;
+use flate2::{read::DeflateDecoder, write::DeflateEncoder};
+use std::io::{Read, Seek, SeekFrom, Write};
+
+#[test]
+fn blocks_round_trip() -> Result<(),Error> {
+    use crate::{Line, Point};
+    use std::io::Cursor;
+    let mut writer = Writer::new(Cursor::new(vec![]), &Header::default())?.with_block_size(200);
+    let mut points = vec![];
+    for i in 0..100 {
+        let point = Point::from_tags(i, ((i % 10) as f32, (i / 10) as f32), &[
+            ("amenity", "cafe"), ("name", "Café"),
+        ])?;
+        writer.write(&point)?;
+        points.push(Feature::Point(point));
+    }
+    let line = Line::from_tags(100, &[("highway", "primary")], &[2.5, 2.5, 20.0, 20.0])?;
+    writer.write(&line)?;
+    let buf = writer.finish()?.into_inner();
+    assert!(container::Reader::new(&buf).is_err());
+
+    let reader = Reader::new(&buf)?;
+    assert_eq!(reader.header().count, 101);
+    assert!(reader.index().len() > 4);
+    assert_eq!(reader.index().iter().map(|b| b.count).sum::<u64>(), 101);
+    assert_eq!(reader.features()?.len(), 101);
+
+    let query = BBox { min_x: 1.5, min_y: 1.5, max_x: 3.5, max_y: 2.5 };
+    let blocks = reader.blocks_in(&query);
+    assert!(blocks.len() < reader.index().len());
+    let mut found = reader.features_in(&query)?;
+    found.sort_by_key(|f| f.id());
+    assert_eq!(found, vec![points[22].clone(), points[23].clone(), Feature::Line(line)]);
+
+    assert_eq!(reader.find(57)?, Some(points[57].clone()));
+    assert_eq!(reader.find(1000)?, None);
+    Ok(())
+}
+
+#[test]
+fn huge_index_count() {
+    let buf = [0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x7f];
+    assert!(read_index(&buf).is_err());
+}
+
+const FOOTER_LEN: usize = 8;
+const DEFAULT_BLOCK_SIZE: usize = 64 * 1024;
+
+/// An axis-aligned bounding box in the coordinates of the positions.
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub struct BBox {
+    pub min_x: f32,
+    pub min_y: f32,
+    pub max_x: f32,
+    pub max_y: f32,
+}
+
+impl Default for BBox {
+    fn default() -> Self {
+        Self {
+            min_x: f32::INFINITY,
+            min_y: f32::INFINITY,
+            max_x: f32::NEG_INFINITY,
+            max_y: f32::NEG_INFINITY,
+        }
+    }
+}
+
+impl BBox {
+    pub fn is_empty(&self) -> bool {
+        self.min_x > self.max_x || self.min_y > self.max_y
+    }
+    pub fn extend(&mut self, (x,y): (f32, f32)) {
+        self.min_x = self.min_x.min(x);
+        self.min_y = self.min_y.min(y);
+        self.max_x = self.max_x.max(x);
+        self.max_y = self.max_y.max(y);
+    }
+    pub fn union(&mut self, other: &BBox) {
+        if other.is_empty() { return }
+        self.extend((other.min_x, other.min_y));
+        self.extend((other.max_x, other.max_y));
+    }
+    pub fn intersects(&self, other: &BBox) -> bool {
+        !self.is_empty() && !other.is_empty()
+            && self.min_x <= other.max_x && other.min_x <= self.max_x
+            && self.min_y <= other.max_y && other.min_y <= self.max_y
+    }
+    /// Like `intersects`, but with x as longitude, also matching copies of
+    /// `other` 360° to either side. Features crossing the antimeridian keep
+    /// unwrapped longitudes past ±180°, so this finds them from both sides.
+    pub fn intersects_wrapped(&self, other: &BBox) -> bool {
+        [0.0, -360.0, 360.0].iter().any(|dx| self.intersects(&BBox {
+            min_x: other.min_x + dx, max_x: other.max_x + dx, ..*other
+        }))
+    }
+    /// The bounding box of an encoded feature.
+    pub fn of(feature: &FeatureRef) -> Self {
+        let mut bbox = Self::default();
+        match feature {
+            FeatureRef::Point(p) => bbox.extend(p.point),
+            FeatureRef::Line(l) => l.positions().for_each(|p| bbox.extend(p)),
+            FeatureRef::Area(a) => a.positions().for_each(|p| bbox.extend(p)),
+        }
+        bbox
+    }
+}
+
+/// Where a block is in the file and what it holds.
+#[derive(Debug,Clone,PartialEq)]
+pub struct BlockInfo {
+    /// byte offset of the compressed block from the start of the header
+    pub offset: u64,
+    /// compressed length in bytes
+    pub length: u64,
+    pub count: u64,
+    pub min_id: u64,
+    pub max_id: u64,
+    pub bbox: BBox,
+}
+
+impl BlockInfo {
+    fn new() -> Self {
+        Self { offset: 0, length: 0, count: 0, min_id: u64::MAX, max_id: 0, bbox: BBox::default() }
+    }
+}
+
+/// Writes a pack with records grouped into deflated blocks, followed by an
+/// index of the blocks and a footer holding the index's offset as a
+/// little-endian u64. Records go into blocks in the order they are written,
+/// so write them sorted by location for tight block bounding boxes.
+///
+/// The index is a varint block count, then for each block varints of its
+/// offset, compressed length, record count, lowest and highest id, and its
+/// bounding box as four little-endian f32s.
+pub struct Writer<W: Write + Seek> {
+    inner: W,
+    start: u64,
+    offset: u64,
+    count: u64,
+    block_size: usize,
+    projection: Projection,
+    block: Vec<u8>,
+    info: BlockInfo,
+    index: Vec<BlockInfo>,
+    buf: Vec<u8>,
+}
+
+impl<W: Write + Seek> Writer<W> {
+    pub fn new(mut inner: W, header: &Header) -> Result<Self,Error> {
+        let header = Header { compression: Compression::Deflate, ..header.clone() };
+        let start = inner.stream_position()?;
+        let mut buf = vec![0u8; header.encoded_len()];
+        header.write_to(&mut buf)?;
+        inner.write_all(&buf)?;
+        Ok(Self {
+            inner,
+            start,
+            offset: buf.len() as u64,
+            count: 0,
+            block_size: DEFAULT_BLOCK_SIZE,
+            projection: header.projection,
+            block: vec![],
+            info: BlockInfo::new(),
+            index: vec![],
+            buf,
+        })
+    }
+    /// Start a new block once this many uncompressed bytes are buffered.
+    pub fn with_block_size(mut self, bytes: usize) -> Self {
+        self.block_size = bytes;
+        self
+    }
+    pub fn write<E: Encode>(&mut self, feature: &E) -> Result<(),Error> {
+        self.buf.clear();
+        feature.write_into(&mut self.buf)?;
+        let record = std::mem::take(&mut self.buf);
+        let result = self.write_record(&record);
+        self.buf = record;
+        result
+    }
+    /// Write an already encoded record. Empty records are left out. Fails if
+    /// the record's projection isn't the header's.
+    pub fn write_record(&mut self, record: &[u8]) -> Result<(),Error> {
+        if record.is_empty() { return Ok(()) }
+        let feature = decode_ref(record)?;
+        container::check_projection(self.projection, &feature)?;
+        let id = feature.id();
+        self.info.min_id = self.info.min_id.min(id);
+        self.info.max_id = self.info.max_id.max(id);
+        self.info.bbox.union(&BBox::of(&feature));
+        self.info.count += 1;
+        let mut len = [0u8; 10];
+        let s = varint::encode(record.len() as u64, &mut len)?;
+        self.block.extend_from_slice(&len[..s]);
+        self.block.extend_from_slice(record);
+        self.count += 1;
+        if self.block.len() >= self.block_size {
+            self.flush_block()?;
+        }
+        Ok(())
+    }
+    fn flush_block(&mut self) -> Result<(),Error> {
+        if self.info.count == 0 { return Ok(()) }
+        let mut encoder = DeflateEncoder::new(vec![], flate2::Compression::default());
+        encoder.write_all(&self.block)?;
+        let compressed = encoder.finish()?;
+        self.inner.write_all(&compressed)?;
+        let mut info = std::mem::replace(&mut self.info, BlockInfo::new());
+        info.offset = self.offset;
+        info.length = compressed.len() as u64;
+        self.offset += info.length;
+        self.index.push(info);
+        self.block.clear();
+        Ok(())
+    }
+    /// Write the last block, the index and the record count and return the
+    /// inner writer.
+    pub fn finish(mut self) -> Result<W,Error> {
+        self.flush_block()?;
+        let mut index = vec![0u8; 10];
+        let s = varint::encode(self.index.len() as u64, &mut index)?;
+        index.truncate(s);
+        for info in self.index.iter() {
+            for x in [info.offset, info.length, info.count, info.min_id, info.max_id].iter() {
+                let mut buf = [0u8; 10];
+                let s = varint::encode(*x, &mut buf)?;
+                index.extend_from_slice(&buf[..s]);
+            }
+            for x in [info.bbox.min_x, info.bbox.min_y, info.bbox.max_x, info.bbox.max_y].iter() {
+                index.extend_from_slice(&x.to_le_bytes());
+            }
+        }
+        self.inner.write_all(&index)?;
+        self.inner.write_all(&self.offset.to_le_bytes())?;
+        let end = self.inner.stream_position()?;
+        self.inner.seek(SeekFrom::Start(self.start + container::COUNT_OFFSET as u64))?;
+        self.inner.write_all(&self.count.to_le_bytes())?;
+        self.inner.seek(SeekFrom::Start(end))?;
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+}
+
+/// Reads a block-compressed pack, decompressing only the blocks asked for.
+pub struct Reader<'a> {
+    header: Header,
+    buf: &'a [u8],
+    index: Vec<BlockInfo>,
+}
+
+impl<'a> Reader<'a> {
+    /// Open a pack written against the built-in type table.
+    pub fn new(buf: &'a [u8]) -> Result<Self,Error> {
+        Self::with_types(buf, &container::type_table())
+    }
+    /// Open a pack written against `types`, listed in id order.
+    pub fn with_types<S: AsRef<str>>(buf: &'a [u8], types: &[S]) -> Result<Self,Error> {
+        let (offset,header) = container::read_header(buf, types)?;
+        if header.compression != Compression::Deflate {
+            failure::bail!["pack is not block compressed, open it with container::Reader"];
+        }
+        if buf.len() < offset + FOOTER_LEN {
+            failure::bail!["not enough bytes to read block index"];
+        }
+        let mut word = [0u8; 8];
+        word.copy_from_slice(&buf[buf.len()-FOOTER_LEN..]);
+        let start = u64::from_le_bytes(word) as usize;
+        if start < offset || start > buf.len() - FOOTER_LEN {
+            failure::bail!["block index offset {} is out of range", start];
+        }
+        let index = read_index(&buf[start..buf.len()-FOOTER_LEN])?;
+        for info in index.iter() {
+            if info.offset + info.length > start as u64 {
+                failure::bail!["block at {} runs past the index", info.offset];
+            }
+        }
+        Ok(Self { header, buf, index })
+    }
+    pub fn header(&self) -> &Header {
+        &self.header
+    }
+    pub fn index(&self) -> &[BlockInfo] {
+        &self.index
+    }
+    /// Decompress block `i`, returning its length-prefixed records.
+    pub fn block(&self, i: usize) -> Result<Vec<u8>,Error> {
+        let info = match self.index.get(i) {
+            Some(info) => info,
+            None => failure::bail!["no block {}", i],
+        };
+        let start = info.offset as usize;
+        let mut decoder = DeflateDecoder::new(&self.buf[start..start + info.length as usize]);
+        let mut block = vec![];
+        decoder.read_to_end(&mut block)?;
+        Ok(block)
+    }
+    /// Indexes of the blocks whose bounding box intersects `bbox`.
+    pub fn blocks_in(&self, bbox: &BBox) -> Vec<usize> {
+        self.index.iter().enumerate()
+            .filter(|(_,info)| self.intersects(&info.bbox, bbox))
+            .map(|(i,_)| i)
+            .collect()
+    }
+    /// Every feature in the pack.
+    pub fn features(&self) -> Result<Vec<Feature>,Error> {
+        let mut features = vec![];
+        for i in 0..self.index.len() {
+            self.each_record(i, |r| { features.push(r.into_owned()?); Ok(()) })?;
+        }
+        Ok(features)
+    }
+    /// The features whose bounding box intersects `bbox`.
+    pub fn features_in(&self, bbox: &BBox) -> Result<Vec<Feature>,Error> {
+        let mut features = vec![];
+        for i in self.blocks_in(bbox) {
+            self.each_record(i, |r| {
+                if self.intersects(&BBox::of(&r), bbox) { features.push(r.into_owned()?) }
+                Ok(())
+            })?;
+        }
+        Ok(features)
+    }
+    /// The feature with `id`, looking only in blocks whose id range covers it.
+    pub fn find(&self, id: u64) -> Result<Option<Feature>,Error> {
+        let mut found = None;
+        for (i,info) in self.index.iter().enumerate() {
+            if id < info.min_id || id > info.max_id { continue }
+            self.each_record(i, |r| {
+                if found.is_none() && r.id() == id { found = Some(r.into_owned()?) }
+                Ok(())
+            })?;
+            if found.is_some() { break }
+        }
+        Ok(found)
+    }
+    fn intersects(&self, a: &BBox, b: &BBox) -> bool {
+        match self.header.projection {
+            Projection::LonLat => a.intersects_wrapped(b),
+            Projection::Mercator => a.intersects(b),
+        }
+    }
+    fn each_record<F>(&self, i: usize, mut f: F) -> Result<(),Error>
+    where F: FnMut(FeatureRef) -> Result<(),Error> {
+        let block = self.block(i)?;
+        for record in Records::new(&block, self.index[i].count) {
+            f(decode_ref(record?)?)?;
+        }
+        Ok(())
+    }
+}
+
+fn read_index(buf: &[u8]) -> Result<Vec<BlockInfo>,Error> {
+    let (mut offset,n) = varint::decode(buf)?;
+    // an entry is at least five one-byte varints and a 16-byte bbox
+    let mut index = Vec::with_capacity((n as usize).min((buf.len() - offset) / 21));
+    for _ in 0..n {
+        let mut values = [0u64; 5];
+        for v in values.iter_mut() {
+            let (s,x) = varint::decode(&buf[offset..])?;
+            offset += s;
+            *v = x;
+        }
+        let mut bbox = [0f32; 4];
+        for v in bbox.iter_mut() {
+            let (s,x) = f32::from_bytes_le(&buf[offset..])?;
+            offset += s;
+            *v = x;
+        }
+        index.push(BlockInfo {
+            offset: values[0],
+            length: values[1],
+            count: values[2],
+            min_id: values[3],
+            max_id: values[4],
+            bbox: BBox { min_x: bbox[0], min_y: bbox[1], max_x: bbox[2], max_y: bbox[3] },
+        });
+    }
+    Ok(index)
+}
@@ -0,0 +1,451 @@
+use crate::{decode, decode_ref, osm_types, varint, Dictionary, Encode, Feature, FeatureRef, Projection};
+use failure::Error;
+use std::io::{Seek, SeekFrom, Write};
+
+#[test]
+fn container_round_trip() -> Result<(),Error> {
+    use crate::{Line, Point};
+    use std::io::Cursor;
+    let point = Point::from_tags(1, (12.25, 54.09), &[("amenity", "cafe")])?;
+    let line = Line::from_tags(2, &[("highway", "primary")], &[0.0, 0.0, 1.0, 1.0])?;
+    let mut writer = Writer::new(Cursor::new(vec![]), &Header::default().with_types())?;
+    writer.write(&point)?;
+    writer.write_record(&crate::encode::node(3, (1.0, 2.0), &[("shop", "bakery")])?)?;
+    writer.write_record(&[])?;
+    writer.write(&line)?;
+    let buf = writer.finish()?.into_inner();
+    assert_eq!(&buf[0..4], MAGIC);
+
+    let reader = Reader::new(&buf)?;
+    assert_eq!(reader.header().count, 3);
+    assert_eq!(reader.header().types.as_ref().map(|t| t.len()), Some(type_table().len()));
+    let features = reader.features().collect::<Result<Vec<Feature>,Error>>()?;
+    assert_eq!(features.len(), 3);
+    assert_eq!(features[0], Feature::Point(point.clone()));
+    assert_eq!(features[2], Feature::Line(line));
+
+    // records must be in the header's projection
+    let options = crate::tags::Options { projection: Projection::Mercator, ..Default::default() };
+    let positions = [0.0, 0.0, 1.0, 1.0];
+    let mercator = Line::from_tags_with(4, &[("highway", "primary")], &positions, &options)?;
+    let mut writer = Writer::new(Cursor::new(vec![]), &Header::default())?;
+    assert!(writer.write(&mercator).is_err());
+    assert!(writer.write(&point).is_ok());
+    let header = Header { projection: Projection::Mercator, ..Header::default() };
+    let mut writer = Writer::new(Cursor::new(vec![]), &header)?;
+    assert!(writer.write(&mercator).is_ok());
+    assert!(writer.write(&point).is_err());
+    Ok(())
+}
+
+#[test]
+fn container_dictionary() -> Result<(),Error> {
+    use crate::{dictionary::TagRef, tags::Options, Line};
+    use std::io::Cursor;
+    let dictionary = Dictionary::new(&["surface", "cobblestone"]);
+    let options = Options {
+        tag_keys: vec!["surface".into()], dictionary: dictionary.clone(), ..Default::default()
+    };
+    let tags = vec![("highway", "residential"), ("surface", "cobblestone")];
+    let line = Line::from_tags_with(1, &tags, &[0.0, 0.0, 1.0, 1.0], &options)?;
+    assert_eq!(line.attributes.tags, vec![(TagRef::Id(0), TagRef::Id(1))]);
+    let header = Header::default().with_dictionary(&dictionary);
+    let mut writer = Writer::new(Cursor::new(vec![]), &header)?;
+    writer.write(&line)?;
+    let buf = writer.finish()?.into_inner();
+    let reader = Reader::new(&buf)?;
+    assert_eq!(reader.header().dictionary_hash, dictionary.hash());
+    let dictionary = reader.header().dictionary();
+    match reader.features().next().unwrap()? {
+        Feature::Line(line) => {
+            assert_eq!(line.attributes.tags(&dictionary)?, vec![("surface", "cobblestone")]);
+        },
+        x => panic!["expected line, got {:?}", x],
+    }
+    // version 1 headers have no dictionary hash
+    let mut v1 = Writer::new(Cursor::new(vec![]), &Header::default())?.finish()?.into_inner();
+    v1[4] = 1;
+    v1.drain(22..30);
+    let (s,header) = Header::from_bytes(&v1)?;
+    assert_eq!((s, header.dictionary_hash), (v1.len(), Dictionary::builtin().hash()));
+    Ok(())
+}
+
+#[test]
+fn container_type_mismatch() -> Result<(),Error> {
+    use std::io::Cursor;
+    let old = vec!["aerialway.cable_car", "amenity.cafe"];
+    let header = Header { types_hash: types_hash(&old), ..Default::default() };
+    let buf = Writer::new(Cursor::new(vec![]), &header)?.finish()?.into_inner();
+    assert!(Reader::new(&buf).is_err());
+    assert_eq!(Reader::with_types(&buf, &old)?.header().count, 0);
+    assert!(Reader::new(b"GRPK").is_err());
+    let custom = Header { dictionary_hash: 1, ..Default::default() };
+    let buf = Writer::new(Cursor::new(vec![]), &custom)?.finish()?.into_inner();
+    assert!(Reader::new(&buf).is_err());
+    // a huge type table count in a short header
+    let mut buf = Writer::new(Cursor::new(vec![]), &Header::default())?.finish()?.into_inner();
+    buf[5] |= TYPES;
+    buf.extend_from_slice(&[0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x7f]);
+    assert!(Header::from_bytes(&buf).is_err());
+    assert!(Reader::new(b"not a pack at all").is_err());
+    Ok(())
+}
+
+pub const MAGIC: &[u8; 4] = b"GRPK";
+/// Format version written by `Writer`. Readers refuse newer versions.
+/// Version 1 headers have no dictionary hash and imply the built-in
+/// dictionary.
+pub const VERSION: u8 = 2;
+
+/// Header flag set when positions are normalized Web Mercator.
+pub const MERCATOR: u8 = 0x01;
+/// Header flag set when the type table is embedded after the header.
+pub const TYPES: u8 = 0x02;
+/// Header flag set when records are grouped into deflated blocks, as written
+/// by `blocks::Writer`.
+pub const DEFLATE: u8 = 0x04;
+/// Header flag set when the tag dictionary is embedded after the type table.
+pub const DICTIONARY: u8 = 0x08;
+
+/// How the records after a header are stored.
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub enum Compression {
+    /// one after another, as written by `Writer`
+    None,
+    /// in deflated blocks with an index, as written by `blocks::Writer`
+    Deflate,
+}
+
+// magic, version, flags, type table hash, record count, dictionary hash
+pub(crate) const COUNT_OFFSET: usize = 4 + 1 + 1 + 8;
+const HEADER_LEN_V1: usize = COUNT_OFFSET + 8;
+const HEADER_LEN: usize = HEADER_LEN_V1 + 8;
+
+/// The start of a pack file: magic bytes `GRPK`, a version byte, a flags
+/// byte, a little-endian u64 hash of the feature type table, a little-endian
+/// u64 record count and a little-endian u64 hash of the tag dictionary used
+/// for attribute tags. Then comes the type table itself when `TYPES` is set
+/// and the dictionary when `DICTIONARY` is set, each a varint count of varint
+/// length-prefixed strings in id order. Records follow, each prefixed with
+/// its varint length.
+#[derive(Debug,Clone,PartialEq)]
+pub struct Header {
+    pub version: u8,
+    pub projection: Projection,
+    pub types_hash: u64,
+    pub types: Option<Vec<String>>,
+    pub dictionary_hash: u64,
+    pub dictionary: Option<Vec<String>>,
+    pub compression: Compression,
+    pub count: u64,
+}
+
+impl Default for Header {
+    fn default() -> Self {
+        Self {
+            version: VERSION,
+            projection: Projection::LonLat,
+            types_hash: types_hash(&type_table()),
+            types: None,
+            dictionary_hash: Dictionary::builtin().hash(),
+            dictionary: None,
+            compression: Compression::None,
+            count: 0,
+        }
+    }
+}
+
+impl Header {
+    /// Embed a copy of the built-in type table.
+    pub fn with_types(mut self) -> Self {
+        let types = type_table();
+        self.types_hash = types_hash(&types);
+        self.types = Some(types.iter().map(|t| t.to_string()).collect());
+        self
+    }
+    /// Embed `dictionary`, which attribute tags in the records refer to.
+    /// Packs using anything but the built-in dictionary must embed it.
+    pub fn with_dictionary(mut self, dictionary: &Dictionary) -> Self {
+        self.dictionary_hash = dictionary.hash();
+        self.dictionary = Some(dictionary.strings().to_vec());
+        self
+    }
+    /// The dictionary attribute tags refer to: the embedded one, or the
+    /// built-in one.
+    pub fn dictionary(&self) -> Dictionary {
+        match &self.dictionary {
+            Some(strings) => Dictionary::new(strings),
+            None => Dictionary::builtin(),
+        }
+    }
+    pub fn flags(&self) -> u8 {
+        let mut flags = 0;
+        if self.projection == Projection::Mercator { flags |= MERCATOR }
+        if self.types.is_some() { flags |= TYPES }
+        if self.compression == Compression::Deflate { flags |= DEFLATE }
+        if self.dictionary.is_some() { flags |= DICTIONARY }
+        flags
+    }
+    pub fn encoded_len(&self) -> usize {
+        HEADER_LEN + table_length(&self.types) + table_length(&self.dictionary)
+    }
+    pub fn write_to(&self, buf: &mut [u8]) -> Result<usize,Error> {
+        if buf.len() < self.encoded_len() {
+            failure::bail!["buffer is too small to write header"];
+        }
+        buf[0..4].copy_from_slice(MAGIC);
+        buf[4] = self.version;
+        buf[5] = self.flags();
+        buf[6..14].copy_from_slice(&self.types_hash.to_le_bytes());
+        buf[14..22].copy_from_slice(&self.count.to_le_bytes());
+        buf[22..30].copy_from_slice(&self.dictionary_hash.to_le_bytes());
+        let mut offset = HEADER_LEN;
+        for table in [&self.types, &self.dictionary].iter().copied().flatten() {
+            offset += write_table(table, &mut buf[offset..])?;
+        }
+        Ok(offset)
+    }
+    pub fn from_bytes(buf: &[u8]) -> Result<(usize,Self),Error> {
+        if buf.len() < HEADER_LEN_V1 || &buf[0..4] != MAGIC {
+            failure::bail!["not a georender pack"];
+        }
+        let version = buf[4];
+        if version > VERSION {
+            failure::bail!["unsupported pack version {}, expected at most {}", version, VERSION];
+        }
+        let flags = buf[5];
+        let mut word = [0u8; 8];
+        word.copy_from_slice(&buf[6..14]);
+        let types_hash = u64::from_le_bytes(word);
+        word.copy_from_slice(&buf[14..22]);
+        let count = u64::from_le_bytes(word);
+        let (mut offset,dictionary_hash) = if version >= 2 {
+            if buf.len() < HEADER_LEN { failure::bail!["not enough bytes to decode header"] }
+            word.copy_from_slice(&buf[22..30]);
+            (HEADER_LEN, u64::from_le_bytes(word))
+        } else {
+            (HEADER_LEN_V1, Dictionary::builtin().hash())
+        };
+        let mut types = None;
+        if flags & TYPES != 0 {
+            let (s,table) = read_table(&buf[offset..])?;
+            offset += s;
+            if self::types_hash(&table) != types_hash {
+                failure::bail!["embedded type table does not match its hash"];
+            }
+            types = Some(table);
+        }
+        let mut dictionary = None;
+        if flags & DICTIONARY != 0 {
+            let (s,table) = read_table(&buf[offset..])?;
+            offset += s;
+            if self::types_hash(&table) != dictionary_hash {
+                failure::bail!["embedded dictionary does not match its hash"];
+            }
+            dictionary = Some(table);
+        }
+        let projection = if flags & MERCATOR != 0 { Projection::Mercator } else { Projection::LonLat };
+        let compression = if flags & DEFLATE != 0 { Compression::Deflate } else { Compression::None };
+        Ok((offset, Self {
+            version, projection, types_hash, types, dictionary_hash, dictionary, compression, count,
+        }))
+    }
+}
+
+fn table_length(table: &Option<Vec<String>>) -> usize {
+    match table {
+        Some(table) => varint::length(table.len() as u64) + table.iter()
+            .map(|t| varint::length(t.len() as u64) + t.len())
+            .sum::<usize>(),
+        None => 0,
+    }
+}
+
+fn write_table(table: &[String], buf: &mut [u8]) -> Result<usize,Error> {
+    let mut offset = varint::encode(table.len() as u64, buf)?;
+    for t in table.iter() {
+        offset += varint::encode(t.len() as u64, &mut buf[offset..])?;
+        buf[offset..offset+t.len()].copy_from_slice(t.as_bytes());
+        offset += t.len();
+    }
+    Ok(offset)
+}
+
+fn read_table(buf: &[u8]) -> Result<(usize,Vec<String>),Error> {
+    let (mut offset,n) = varint::decode(buf)?;
+    // each entry takes at least one byte, so don't trust `n` for capacity
+    let mut table = Vec::with_capacity((n as usize).min(buf.len() - offset));
+    for _ in 0..n {
+        let (s,len) = varint::decode(&buf[offset..])?;
+        offset += s;
+        let end = (len as usize).checked_add(offset)
+            .filter(|end| *end <= buf.len())
+            .ok_or_else(|| failure::format_err!["not enough bytes to decode table"])?;
+        table.push(String::from_utf8(buf[offset..end].to_vec())?);
+        offset = end;
+    }
+    Ok((offset,table))
+}
+
+/// The built-in feature type names, indexed by id.
+pub fn type_table() -> Vec<&'static str> {
+    osm_types::TYPES.to_vec()
+}
+
+/// 64-bit FNV-1a hash of type names in id order, each followed by a zero byte.
+pub fn types_hash<S: AsRef<str>>(types: &[S]) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for t in types.iter() {
+        for b in t.as_ref().bytes().chain(std::iter::once(0)) {
+            hash ^= b as u64;
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+    }
+    hash
+}
+
+/// Writes a header and length-prefixed records, filling in the record count
+/// when finished.
+pub struct Writer<W: Write + Seek> {
+    inner: W,
+    start: u64,
+    count: u64,
+    projection: Projection,
+    buf: Vec<u8>,
+}
+
+impl<W: Write + Seek> Writer<W> {
+    pub fn new(mut inner: W, header: &Header) -> Result<Self,Error> {
+        let start = inner.stream_position()?;
+        let mut buf = vec![0u8; header.encoded_len()];
+        header.write_to(&mut buf)?;
+        inner.write_all(&buf)?;
+        Ok(Self { inner, start, count: 0, projection: header.projection, buf })
+    }
+    pub fn write<E: Encode>(&mut self, feature: &E) -> Result<(),Error> {
+        self.buf.clear();
+        feature.write_into(&mut self.buf)?;
+        let record = std::mem::take(&mut self.buf);
+        let result = self.write_record(&record);
+        self.buf = record;
+        result
+    }
+    /// Write an already encoded record, such as one from `encode::way`.
+    /// Empty records, as returned for skipped elements, are left out. Fails
+    /// if the record's projection isn't the header's.
+    pub fn write_record(&mut self, record: &[u8]) -> Result<(),Error> {
+        if record.is_empty() { return Ok(()) }
+        check_projection(self.projection, &decode_ref(record)?)?;
+        let mut len = [0u8; 10];
+        let s = varint::encode(record.len() as u64, &mut len)?;
+        self.inner.write_all(&len[..s])?;
+        self.inner.write_all(record)?;
+        self.count += 1;
+        Ok(())
+    }
+    /// Write the record count into the header and return the inner writer.
+    pub fn finish(mut self) -> Result<W,Error> {
+        let end = self.inner.stream_position()?;
+        self.inner.seek(SeekFrom::Start(self.start + COUNT_OFFSET as u64))?;
+        self.inner.write_all(&self.count.to_le_bytes())?;
+        self.inner.seek(SeekFrom::Start(end))?;
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+}
+
+/// Fail unless `feature` is stored in `projection`, as the header says.
+pub(crate) fn check_projection(projection: Projection, feature: &FeatureRef) -> Result<(),Error> {
+    let found = feature.attributes()?.projection;
+    if found != projection {
+        failure::bail![
+            "record {} is in {:?} but the header says {:?}", feature.id(), found, projection
+        ];
+    }
+    Ok(())
+}
+
+/// Reads the records of a pack, refusing packs written with a different
+/// type table.
+pub struct Reader<'a> {
+    header: Header,
+    records: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    /// Open a pack written against the built-in type table.
+    pub fn new(buf: &'a [u8]) -> Result<Self,Error> {
+        Self::with_types(buf, &type_table())
+    }
+    /// Open a pack written against `types`, listed in id order.
+    pub fn with_types<S: AsRef<str>>(buf: &'a [u8], types: &[S]) -> Result<Self,Error> {
+        let (offset,header) = read_header(buf, types)?;
+        if header.compression != Compression::None {
+            failure::bail!["pack is block compressed, open it with blocks::Reader"];
+        }
+        Ok(Self { header, records: &buf[offset..] })
+    }
+    pub fn header(&self) -> &Header {
+        &self.header
+    }
+    /// The encoded records, for `decode` or `decode_ref`.
+    pub fn records(&self) -> Records<'a> {
+        Records::new(self.records, self.header.count)
+    }
+    pub fn features(&self) -> impl Iterator<Item=Result<Feature,Error>> + 'a {
+        self.records().map(|r| r.and_then(decode))
+    }
+}
+
+// parse a header and check it was written with `types` and either the
+// built-in or an embedded dictionary
+pub(crate) fn read_header<S: AsRef<str>>(buf: &[u8], types: &[S]) -> Result<(usize,Header),Error> {
+    let (offset,header) = Header::from_bytes(buf)?;
+    let expected = types_hash(types);
+    if header.types_hash != expected {
+        failure::bail![
+            "pack was written with a different type table ({:016x}, expected {:016x})",
+            header.types_hash, expected
+        ];
+    }
+    if header.dictionary.is_none() && header.dictionary_hash != Dictionary::builtin().hash() {
+        failure::bail![
+            "pack was written with a dictionary ({:016x}) that is neither built in nor embedded",
+            header.dictionary_hash
+        ];
+    }
+    Ok((offset,header))
+}
+
+/// Iterator over length-prefixed records.
+pub struct Records<'a> {
+    buf: &'a [u8],
+    remaining: u64,
+}
+
+impl<'a> Records<'a> {
+    pub(crate) fn new(buf: &'a [u8], count: u64) -> Self {
+        Self { buf, remaining: count }
+    }
+}
+
+impl<'a> Iterator for Records<'a> {
+    type Item = Result<&'a [u8],Error>;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 { return None }
+        self.remaining -= 1;
+        let (s,len) = match varint::decode(self.buf) {
+            Ok(x) => x,
+            Err(e) => { self.remaining = 0; return Some(Err(e)) },
+        };
+        let end = s + len as usize;
+        if end > self.buf.len() {
+            self.remaining = 0;
+            return Some(Err(failure::format_err!["not enough bytes to read record"]));
+        }
+        let record = &self.buf[s..end];
+        self.buf = &self.buf[end..];
+        Some(Ok(record))
+    }
+}
@@ -0,0 +1,107 @@
+use failure::Error;
+use std::collections::HashMap;
+
+#[test]
+fn builtin_dictionary() {
+    let dict = Dictionary::builtin();
+    let id = dict.id("asphalt").unwrap();
+    assert_eq!(dict.get(id), Some("asphalt"));
+    assert_eq!(dict.id("not in the table"), None);
+    for key in DEFAULT_TAG_KEYS.iter() {
+        assert!(dict.id(key).is_some());
+    }
+    let custom = Dictionary::new(&["surface", "cobblestone"]);
+    assert_eq!(custom.id("cobblestone"), Some(1));
+    assert_eq!(custom.strings(), &["surface".to_string(), "cobblestone".to_string()]);
+}
+
+/// Keys kept by `tags::Options::tag_keys` when asked for the usual set.
+pub const DEFAULT_TAG_KEYS: [&str; 5] = ["surface", "access", "oneway", "bridge", "tunnel"];
+
+// Ids are indexes into this table, so entries may only ever be appended.
+const BUILTIN: [&str; 60] = [
+    "surface", "access", "oneway", "bridge", "tunnel",
+    "yes", "no", "-1",
+    // surface
+    "paved", "asphalt", "concrete", "concrete:plates", "concrete:lanes",
+    "paving_stones", "sett", "cobblestone", "unhewn_cobblestone", "metal", "wood",
+    "unpaved", "compacted", "fine_gravel", "gravel", "pebblestone", "rock", "dirt",
+    "earth", "grass", "grass_paver", "ground", "mud", "sand", "woodchips", "snow",
+    "ice", "salt", "clay", "artificial_turf", "tartan",
+    // access
+    "private", "permissive", "destination", "delivery", "customers",
+    "designated", "agricultural", "forestry", "permit", "discouraged", "use_sidepath",
+    "unknown", "emergency",
+    // oneway
+    "reversible", "alternating",
+    // bridge
+    "viaduct", "boardwalk", "movable", "aqueduct",
+    // tunnel
+    "culvert", "building_passage",
+];
+
+/// A table of strings that tag keys and values are stored as indexes into,
+/// either the built-in table or one shipped alongside a set of features.
+#[derive(Debug,Clone,PartialEq)]
+pub struct Dictionary {
+    strings: Vec<String>,
+    index: HashMap<String,u64>,
+}
+
+impl Default for Dictionary {
+    fn default() -> Self {
+        Self::builtin()
+    }
+}
+
+impl Dictionary {
+    pub fn new<S: AsRef<str>>(strings: &[S]) -> Self {
+        let strings = strings.iter().map(|s| s.as_ref().to_string()).collect::<Vec<_>>();
+        let mut index = HashMap::new();
+        for (i,s) in strings.iter().enumerate() {
+            index.entry(s.clone()).or_insert(i as u64);
+        }
+        Self { strings, index }
+    }
+    pub fn builtin() -> Self {
+        Self::new(&BUILTIN)
+    }
+    pub fn id(&self, s: &str) -> Option<u64> {
+        self.index.get(s).copied()
+    }
+    pub fn get(&self, id: u64) -> Option<&str> {
+        self.strings.get(id as usize).map(|s| s.as_str())
+    }
+    pub fn strings(&self) -> &[String] {
+        &self.strings
+    }
+    /// Identifies the table, hashed the same way as `container::types_hash`.
+    pub fn hash(&self) -> u64 {
+        crate::container::types_hash(&self.strings)
+    }
+
+    /// Look up `s`, keeping it inline when it isn't in the table.
+    pub fn tag_ref(&self, s: &str) -> TagRef {
+        match self.id(s) {
+            Some(id) => TagRef::Id(id),
+            None => TagRef::Str(s.to_string()),
+        }
+    }
+    pub fn resolve<'a>(&'a self, r: &'a TagRef) -> Result<&'a str,Error> {
+        match r {
+            TagRef::Id(id) => match self.get(*id) {
+                Some(s) => Ok(s),
+                None => failure::bail!["dictionary has no entry {}", id],
+            },
+            TagRef::Str(s) => Ok(s.as_str()),
+        }
+    }
+}
+
+/// A tag key or value, either an index into a `Dictionary` or a string that
+/// wasn't found in it.
+#[derive(Debug,Clone,PartialEq)]
+pub enum TagRef {
+    Id(u64),
+    Str(String),
+}
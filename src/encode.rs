@@ -1,12 +1,44 @@
-use crate::{Area, Line, Point, Member, MemberRole, tags};
-use desert::ToBytesLE;
+use crate::{antimeridian, area_builder, triangulate::Triangulation, Area, Line, Point, Feature, Attributes, Member, MemberRole, Encode, Projection, tags};
 use failure::Error;
 use osm_is_area;
 use std::collections::HashMap;
 
 pub fn node(id: u64, point: (f32, f32), tags: &[(&str, &str)]) -> Result<Vec<u8>, Error> {
-    let node = Point::from_tags(id, point, &tags)?;
-    return node.to_bytes_le();
+    let mut buf = vec![];
+    node_into(id, point, tags, &mut buf)?;
+    Ok(buf)
+}
+
+pub fn node_into(
+    id: u64,
+    point: (f32, f32),
+    tags: &[(&str, &str)],
+    buf: &mut Vec<u8>,
+) -> Result<usize, Error> {
+    node_with_into(id, point, tags, &tags::DEFAULT_OPTIONS, buf)
+}
+
+/// Like `node`, parsing tags with `options`.
+pub fn node_with(
+    id: u64,
+    point: (f32, f32),
+    tags: &[(&str, &str)],
+    options: &tags::Options,
+) -> Result<Vec<u8>, Error> {
+    let mut buf = vec![];
+    node_with_into(id, point, tags, options, &mut buf)?;
+    Ok(buf)
+}
+
+pub fn node_with_into(
+    id: u64,
+    point: (f32, f32),
+    tags: &[(&str, &str)],
+    options: &tags::Options,
+    buf: &mut Vec<u8>,
+) -> Result<usize, Error> {
+    let node = Point::from_tags_with(id, point, tags, options)?;
+    node.write_into(buf)
 }
 
 pub fn node_from_parsed(
@@ -15,8 +47,42 @@ pub fn node_from_parsed(
     feature_type: u64,
     labels: &[u8],
 ) -> Result<Vec<u8>, Error> {
+    let mut buf = vec![];
+    node_from_parsed_into(id, point, feature_type, labels, &mut buf)?;
+    Ok(buf)
+}
+
+pub fn node_from_parsed_into(
+    id: u64,
+    point: (f32, f32),
+    feature_type: u64,
+    labels: &[u8],
+    buf: &mut Vec<u8>,
+) -> Result<usize, Error> {
     let node = Point::new(id, point, feature_type, labels);
-    return node.to_bytes_le();
+    node.write_into(buf)
+}
+
+#[test]
+fn encode_into_shared_buffer() -> Result<(),Error> {
+    use crate::decode_ref;
+    let tags = vec![("source", "bing"), ("highway", "residential")];
+    let refs = vec![1, 5, 3];
+    let mut deps = HashMap::new();
+    deps.insert(1, (31.1848, 29.89774));
+    deps.insert(5, (31.184889, 29.898802));
+    deps.insert(3, (31.184858, 29.89839));
+    let mut buf = vec![];
+    let n0 = node_into(1831881213, (12.253938, 54.090065), &[("place", "city")], &mut buf)?;
+    let n1 = way_into(234941233, &tags, &refs, &deps, &mut buf)?;
+    assert_eq!(n0 + n1, buf.len());
+    assert_eq!(&buf[n0..], &way(234941233, &tags, &refs, &deps)?[..]);
+    assert_eq!(decode_ref(&buf)?.id(), 1831881213);
+    assert_eq!(decode_ref(&buf[n0..])?.id(), 234941233);
+    // a single-ref way has nothing to encode
+    assert_eq!(way_into(1, &tags, &refs[0..1], &deps, &mut buf)?, 0);
+    assert_eq!(n0 + n1, buf.len());
+    Ok(())
 }
 
 #[test]
@@ -24,9 +90,9 @@ fn encode_way_line() {
     let tags = vec![("source", "bing"), ("highway", "residential")];
     let refs = vec![1, 5, 3];
     let mut deps = HashMap::new();
-    deps.insert(1, (31.184799400000003, 29.897739500000004));
-    deps.insert(5, (31.184888100000002, 29.898801400000004));
-    deps.insert(3, (31.184858400000003, 29.8983899));
+    deps.insert(1, (31.1848, 29.89774));
+    deps.insert(5, (31.184889, 29.898802));
+    deps.insert(3, (31.184858, 29.89839));
     let bytes = way(234941233, &tags, &refs, &deps).unwrap();
     assert_eq!(
         "029b03b1d6837003787af941922eef41a77af941bf30ef41977af941e72fef4100",
@@ -34,30 +100,84 @@ fn encode_way_line() {
     );
 }
 
+#[test]
+fn encode_way_line_flags() -> Result<(),Error> {
+    use crate::{decode, LineFlags, Oneway};
+    let tags = vec![("highway", "primary"), ("tunnel", "yes"), ("layer", "-1"), ("oneway", "yes")];
+    let refs = vec![1, 5, 3];
+    let mut deps = HashMap::new();
+    deps.insert(1, (31.1848, 29.89774));
+    deps.insert(5, (31.184889, 29.898802));
+    deps.insert(3, (31.184858, 29.89839));
+    assert_eq!(way(234941233, &tags, &refs, &deps)?[0], 0x02);
+    let options = tags::Options { attributes: true, ..Default::default() };
+    match decode(&way_with(234941233, &tags, &refs, &deps, &options)?)? {
+        Feature::Line(line) => {
+            assert_eq!(line.flags, LineFlags { bridge: false, tunnel: true, oneway: Oneway::Forward });
+            assert_eq!(line.attributes.layer, Some(-1));
+        },
+        _ => panic!["expected line"],
+    }
+    Ok(())
+}
+
+#[test]
+fn encode_with_options() -> Result<(),Error> {
+    use crate::{decode, Feature, MemberType, Projection};
+    let options = tags::Options { attributes: true, projection: Projection::Mercator, ..Default::default() };
+    match decode(&node_with(1, (0.0, 0.0), &[("amenity", "cafe")], &options)?)? {
+        Feature::Point(point) => {
+            assert_eq!(point.point, (0.5, 0.5));
+            assert_eq!(point.attributes.projection, Projection::Mercator);
+        },
+        _ => panic!["expected point"],
+    }
+    let mut nodes = HashMap::new();
+    nodes.insert(1, (0.0, 0.0));
+    nodes.insert(2, (1.0, 0.0));
+    nodes.insert(3, (1.0, 1.0));
+    let mut ways = HashMap::new();
+    ways.insert(10, vec![1,2,3,1]);
+    let tags = [("type", "multipolygon"), ("building", "yes"), ("height", "12")];
+    let members = [Member::new(10, MemberRole::Outer(), MemberType::Way())];
+    match decode(&relation_with(20, &tags, &members, &nodes, &ways, &options)?)? {
+        Feature::Area(area) => {
+            assert_eq!(area.attributes.height, Some(12.0));
+            assert_eq!(area.attributes.projection, Projection::Mercator);
+            assert!(area.positions.iter().all(|p| *p >= 0.0 && *p <= 1.0));
+        },
+        _ => panic!["expected area"],
+    }
+    assert_eq!(relation(20, &tags, &members, &nodes, &ways)?[0], 0x03);
+    Ok(())
+}
+
 #[test]
 fn encode_way_area() -> Result<(),Error> {
     use crate::{decode, Feature, osm_types::get_types};
     let tags = vec![("source", "bing"), ("leisure", "park")];
     let refs = vec![1, 5, 3, 1];
     let mut deps = HashMap::new();
-    deps.insert(1, (31.184799400000003, 29.897739500000004));
-    deps.insert(5, (31.184888100000002, 29.898801400000004));
-    deps.insert(3, (31.184858400000003, 29.8983899));
+    deps.insert(1, (31.1848, 29.89774));
+    deps.insert(5, (31.184889, 29.898802));
+    deps.insert(3, (31.184858, 29.89839));
     let feature_type = *get_types().get("leisure.park").unwrap();
     let expected = Feature::Area(Area {
         id: 234941233,
         feature_type,
         labels: vec![0],
+        // the clockwise way is written counter-clockwise
         positions: vec![
-            31.184799400000003, 29.897739500000004,
-            31.184888100000002, 29.898801400000004,
-            31.184858400000003, 29.8983899,
+            31.1848, 29.89774,
+            31.184858, 29.89839,
+            31.184889, 29.898802,
         ],
-        cells: vec![1,0,2],
+        cells: vec![1,2,0],
+        attributes: Default::default(),
     });
     assert_eq![&expected, &decode(&way(234941233, &tags, &refs, &deps)?)?];
     assert_eq![&expected, &decode(
-        &way_from_parsed(234941233, feature_type, true, &vec![0], &refs, &deps)?
+        &way_from_parsed(234941233, feature_type, true, &[0], &refs, &deps)?
     )?];
     Ok(())
 }
@@ -68,24 +188,64 @@ pub fn way(
     refs: &[u64],
     deps: &HashMap<u64, (f32, f32)>,
 ) -> Result<Vec<u8>, Error> {
-    let len = refs.len();
-    if osm_is_area::way(tags, refs) {
-        // omit the duplicated ref for areas (first == last):
-        let fixed_refs = {
-            if refs.first() == refs.last() { &refs[0..refs.len()-1] }
-            else { &refs }
-        };
-        let positions = get_way_positions(&fixed_refs, &deps)?;
-        let mut area = Area::from_tags(id, &tags)?;
-        area.push(&positions, &vec![]);
-        area.to_bytes_le()
-    } else if len > 1 {
-        let positions = get_way_positions(&refs, &deps)?;
-        let line = Line::from_tags(id, &tags, &positions)?;
-        line.to_bytes_le()
-    } else {
-        Ok(vec![])
-    }
+    let mut buf = vec![];
+    way_into(id, tags, refs, deps, &mut buf)?;
+    Ok(buf)
+}
+
+pub fn way_into(
+    id: u64,
+    tags: &[(&str, &str)],
+    refs: &[u64],
+    deps: &HashMap<u64, (f32, f32)>,
+    buf: &mut Vec<u8>,
+) -> Result<usize, Error> {
+    way_with_into(id, tags, refs, deps, &tags::DEFAULT_OPTIONS, buf).map(|(n,_)| n)
+}
+
+/// Like `way`, parsing tags with `options`, which can opt in to attributes
+/// and line flags or pick a projection.
+pub fn way_with(
+    id: u64,
+    tags: &[(&str, &str)],
+    refs: &[u64],
+    deps: &HashMap<u64, (f32, f32)>,
+    options: &tags::Options,
+) -> Result<Vec<u8>, Error> {
+    let mut buf = vec![];
+    way_with_into(id, tags, refs, deps, options, &mut buf)?;
+    Ok(buf)
+}
+
+/// Like `way_with`, appending to `buf`. Also returns how an area's rings were
+/// triangulated, so a `Triangulation::Fallback` from broken data can be
+/// logged.
+pub fn way_with_into(
+    id: u64,
+    tags: &[(&str, &str)],
+    refs: &[u64],
+    deps: &HashMap<u64, (f32, f32)>,
+    options: &tags::Options,
+    buf: &mut Vec<u8>,
+) -> Result<(usize, Option<Triangulation>), Error> {
+    let is_area = osm_is_area::way(tags, refs);
+    if !is_area && refs.len() <= 1 { return Ok((0, None)) }
+    let (feature_type, labels) = tags::parse_with(tags, options)?;
+    let (feature, method) = way_feature(id, feature_type, is_area, &labels, refs, deps)?;
+    let n = match feature {
+        Some(Feature::Line(mut line)) => {
+            line.apply_options(tags, options);
+            line.write_into(buf)?
+        },
+        Some(Feature::Area(mut area)) => {
+            area.attributes = Attributes { projection: Projection::LonLat, ..options.attributes(tags) };
+            area.project(options.projection);
+            area.write_into(buf)?
+        },
+        Some(feature) => feature.write_into(buf)?,
+        None => 0,
+    };
+    Ok((n, method))
 }
 
 pub fn way_from_parsed(
@@ -96,23 +256,52 @@ pub fn way_from_parsed(
     refs: &[u64],
     deps: &HashMap<u64, (f32, f32)>,
 ) -> Result<Vec<u8>, Error> {
+    let mut buf = vec![];
+    way_from_parsed_into(id, feature_type, is_area, labels, refs, deps, &mut buf)?;
+    Ok(buf)
+}
+
+pub fn way_from_parsed_into(
+    id: u64,
+    feature_type: u64,
+    is_area: bool,
+    labels: &[u8],
+    refs: &[u64],
+    deps: &HashMap<u64, (f32, f32)>,
+    buf: &mut Vec<u8>,
+) -> Result<usize, Error> {
+    match way_feature(id, feature_type, is_area, labels, refs, deps)? {
+        (Some(feature), _) => feature.write_into(buf),
+        (None, _) => Ok(0),
+    }
+}
+
+fn way_feature(
+    id: u64,
+    feature_type: u64,
+    is_area: bool,
+    labels: &[u8],
+    refs: &[u64],
+    deps: &HashMap<u64, (f32, f32)>,
+) -> Result<(Option<Feature>, Option<Triangulation>), Error> {
     let len = refs.len();
     if is_area {
         // omit the duplicated ref for areas (first == last):
         let fixed_refs = {
             if refs.first() == refs.last() { &refs[0..refs.len()-1] }
-            else { &refs }
+            else { refs }
         };
-        let positions = get_way_positions(&fixed_refs, &deps)?;
+        let positions = get_way_positions(fixed_refs, deps)?;
         let mut area = Area::new(id, feature_type, labels);
-        area.push(&positions, &vec![]);
-        return area.to_bytes_le();
+        let method = area_builder::with_builder(|builder| builder.push(&mut area, &positions, &[]))?;
+        Ok((Some(Feature::Area(area)), Some(method)))
     } else if len > 1 {
-        let positions = get_way_positions(&refs, &deps)?;
+        let mut positions = get_way_positions(refs, deps)?;
+        antimeridian::unwrap(&mut positions, &[]);
         let line = Line::new(id, feature_type, labels, &positions);
-        return line.to_bytes_le();
+        Ok((Some(Feature::Line(line)), None))
     } else {
-        return Ok(vec![]);
+        Ok((None, None))
     }
 }
 
@@ -123,10 +312,58 @@ pub fn relation(
     nodes: &HashMap<u64, (f32, f32)>,
     ways: &HashMap<u64, Vec<u64>>,
 ) -> Result<Vec<u8>, Error> {
+    let mut buf = vec![];
+    relation_into(id, tags, members, nodes, ways, &mut buf)?;
+    Ok(buf)
+}
+
+pub fn relation_into(
+    id: u64,
+    tags: &[(&str, &str)],
+    members: &[Member],
+    nodes: &HashMap<u64, (f32, f32)>,
+    ways: &HashMap<u64, Vec<u64>>,
+    buf: &mut Vec<u8>,
+) -> Result<usize, Error> {
+    relation_with_into(id, tags, members, nodes, ways, &tags::DEFAULT_OPTIONS, buf).map(|(n,_)| n)
+}
+
+/// Like `relation`, parsing tags with `options`.
+pub fn relation_with(
+    id: u64,
+    tags: &[(&str, &str)],
+    members: &[Member],
+    nodes: &HashMap<u64, (f32, f32)>,
+    ways: &HashMap<u64, Vec<u64>>,
+    options: &tags::Options,
+) -> Result<Vec<u8>, Error> {
+    let mut buf = vec![];
+    relation_with_into(id, tags, members, nodes, ways, options, &mut buf)?;
+    Ok(buf)
+}
+
+/// Like `relation_with`, appending to `buf`. Also returns the worst
+/// triangulation of the relation's polygons, as `way_with_into` does.
+pub fn relation_with_into(
+    id: u64,
+    tags: &[(&str, &str)],
+    members: &[Member],
+    nodes: &HashMap<u64, (f32, f32)>,
+    ways: &HashMap<u64, Vec<u64>>,
+    options: &tags::Options,
+    buf: &mut Vec<u8>,
+) -> Result<(usize, Option<Triangulation>), Error> {
     // osm_is_area only checks members.is_empty():
-    let is_area = osm_is_area::relation(&tags, &vec![0]);
-    let (feature_type, labels) = tags::parse(tags)?;
-    relation_from_parsed(id, feature_type, is_area, &labels, members, nodes, ways)
+    let is_area = osm_is_area::relation(tags, &[0]);
+    let (feature_type, labels) = tags::parse_with(tags, options)?;
+    match relation_area(id, feature_type, is_area, &labels, members, nodes, ways)? {
+        Some((mut area, method)) => {
+            area.attributes = Attributes { projection: Projection::LonLat, ..options.attributes(tags) };
+            area.project(options.projection);
+            Ok((area.write_into(buf)?, Some(method)))
+        },
+        None => Ok((0, None)),
+    }
 }
 
 pub fn relation_from_parsed(
@@ -138,11 +375,42 @@ pub fn relation_from_parsed(
     nodes: &HashMap<u64, (f32, f32)>,
     ways: &HashMap<u64, Vec<u64>>,
 ) -> Result<Vec<u8>, Error> {
-    if members.is_empty() || !is_area { return Ok(vec![]) }
+    let mut buf = vec![];
+    relation_from_parsed_into(id, feature_type, is_area, labels, members, nodes, ways, &mut buf)?;
+    Ok(buf)
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn relation_from_parsed_into(
+    id: u64,
+    feature_type: u64,
+    is_area: bool,
+    labels: &[u8],
+    members: &[Member],
+    nodes: &HashMap<u64, (f32, f32)>,
+    ways: &HashMap<u64, Vec<u64>>,
+    buf: &mut Vec<u8>,
+) -> Result<usize, Error> {
+    match relation_area(id, feature_type, is_area, labels, members, nodes, ways)? {
+        Some((area, _)) => area.write_into(buf),
+        None => Ok(0),
+    }
+}
+
+fn relation_area(
+    id: u64,
+    feature_type: u64,
+    is_area: bool,
+    labels: &[u8],
+    members: &[Member],
+    nodes: &HashMap<u64, (f32, f32)>,
+    ways: &HashMap<u64, Vec<u64>>,
+) -> Result<Option<(Area, Triangulation)>, Error> {
+    if members.is_empty() || !is_area { return Ok(None) }
     let mut mmembers: Vec<Member> = members.to_vec();
     Member::drain(&mut mmembers, ways);
     if !mmembers.iter().any(|m| m.role == MemberRole::Outer()) {
-        return Ok(vec![]); // skip relations with no outers
+        return Ok(None); // skip relations with no outers
     }
     mmembers = Member::sort(&mmembers, ways);
 
@@ -174,6 +442,12 @@ pub fn relation_from_parsed(
     };
 
     let mut area = Area::new(id, feature_type, labels);
+    let mut method = Triangulation::Earcut;
+    let mut push = |area: &mut Area, positions: &[f32], holes: &[usize]| -> Result<(),Error> {
+        let m = area_builder::with_builder(|builder| builder.push(area, positions, holes))?;
+        method = method.worst(m);
+        Ok(())
+    };
     let mut ref0 = points.first().map(|(_,id)| id);
     let mut refi = 0;
     let mut positions = vec![];
@@ -191,11 +465,11 @@ pub fn relation_from_parsed(
                     positions.push(pt.0);
                     positions.push(pt.1);
                 } else {
-                    return Ok(vec![]);
+                    return Ok(None);
                 }
             },
             (Some(MemberRole::Inner()),MemberRole::Outer()) => {
-                area.push(&positions, &holes);
+                push(&mut area, &positions, &holes)?;
                 positions.clear();
                 holes.clear();
                 ref0 = Some(c_id);
@@ -204,7 +478,7 @@ pub fn relation_from_parsed(
                     positions.push(pt.0);
                     positions.push(pt.1);
                 } else {
-                    return Ok(vec![]);
+                    return Ok(None);
                 }
             },
             (_,MemberRole::Inner()) => {
@@ -219,14 +493,14 @@ pub fn relation_from_parsed(
                     positions.push(pt.0);
                     positions.push(pt.1);
                 } else {
-                    return Ok(vec![]);
+                    return Ok(None);
                 }
             },
             (_,MemberRole::Outer()) => {
                 if ref0 == Some(c_id) && refi != i {
                     // closed loop
                     if n_role == Some(&MemberRole::Outer()) {
-                        area.push(&positions, &holes);
+                        push(&mut area, &positions, &holes)?;
                         positions.clear();
                         holes.clear();
                     }
@@ -236,16 +510,16 @@ pub fn relation_from_parsed(
                     positions.push(pt.0);
                     positions.push(pt.1);
                 } else {
-                    return Ok(vec![]);
+                    return Ok(None);
                 }
             },
             (_,MemberRole::Unused()) => {},
         }
     }
     if !positions.is_empty() {
-        area.push(&positions, &holes);
+        push(&mut area, &positions, &holes)?;
     }
-    return area.to_bytes_le();
+    Ok(Some((area, method)))
 }
 
 fn get_way_positions(
@@ -256,10 +530,10 @@ fn get_way_positions(
     let xrefs = if refs.first() == refs.last() {
         &refs[0..refs.len()-1]
     } else {
-        &refs[..]
+        refs
     };
     for r in xrefs.iter() {
-        match nodes.get(&r) {
+        match nodes.get(r) {
             Some((lon, lat)) => {
                 positions.push(*lon);
                 positions.push(*lat);
@@ -267,5 +541,5 @@ fn get_way_positions(
             None => bail!("Could not find dep for {}", &r),
         }
     }
-    return Ok(positions);
+    Ok(positions)
 }
@@ -0,0 +1,121 @@
+use crate::{winding, Area, Attributes};
+
+#[test]
+fn extrude_square() {
+    let mut area = Area::new(1, 0, &[0]);
+    area.push(&[0.0, 0.0, 1.0, 0.0, 1.0, 1.0, 0.0, 1.0], &[]);
+    let mesh = extrude(&area, 2.0, 10.0);
+    // 4 roof vertices and 4 per wall
+    assert_eq!(mesh.len(), 4 + 4*4);
+    assert_eq!(mesh.cells.len(), 2*3 + 4*2*3);
+    assert_eq!(mesh.positions.len(), mesh.normals.len());
+    assert!(mesh.positions.chunks(3).all(|p| p[2] == 2.0 || p[2] == 10.0));
+    // roof faces up and walls face away from the middle
+    for (p,n) in mesh.positions.chunks(3).zip(mesh.normals.chunks(3)) {
+        if n[2] == 1.0 {
+            assert_eq!(p[2], 10.0);
+        } else {
+            assert_eq!(n[2], 0.0);
+            let out = (p[0] - 0.5) * n[0] + (p[1] - 0.5) * n[1];
+            assert!(out > 0.0);
+        }
+    }
+    // every triangle winds counter-clockwise seen along its normal
+    for c in mesh.cells.chunks(3) {
+        let v = |i: u32| &mesh.positions[i as usize * 3..i as usize * 3 + 3];
+        let (a,b,d) = (v(c[0]), v(c[1]), v(c[2]));
+        let u = [b[0]-a[0], b[1]-a[1], b[2]-a[2]];
+        let w = [d[0]-a[0], d[1]-a[1], d[2]-a[2]];
+        let cross = [u[1]*w[2]-u[2]*w[1], u[2]*w[0]-u[0]*w[2], u[0]*w[1]-u[1]*w[0]];
+        let n = &mesh.normals[c[0] as usize * 3..c[0] as usize * 3 + 3];
+        assert!(cross[0]*n[0] + cross[1]*n[1] + cross[2]*n[2] > 0.0);
+    }
+}
+
+#[test]
+fn building_heights() {
+    let attrs = |height, min_height, levels| Attributes {
+        height, min_height, levels, ..Default::default()
+    };
+    assert_eq!(heights(&attrs(Some(12.0), Some(3.0), None)), Some((3.0, 12.0)));
+    assert_eq!(heights(&attrs(None, None, Some(4.0))), Some((0.0, 12.0)));
+    assert_eq!(heights(&attrs(None, None, None)), None);
+}
+
+/// Height of one building level in meters, for buildings tagged with
+/// `building:levels` but no `height`.
+pub const LEVEL_HEIGHT: f32 = 3.0;
+
+/// An extruded triangle mesh ready to upload to a GPU: `positions` and
+/// `normals` hold `[x,y,z]` per vertex and `cells` three vertex indexes per
+/// triangle, counter-clockwise when seen from outside.
+#[derive(Debug,Clone,Default,PartialEq)]
+pub struct Mesh {
+    pub positions: Vec<f32>,
+    pub normals: Vec<f32>,
+    pub cells: Vec<u32>,
+}
+
+impl Mesh {
+    /// The number of vertices.
+    pub fn len(&self) -> usize {
+        self.positions.len() / 3
+    }
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+    fn vertex(&mut self, x: f32, y: f32, z: f32, normal: [f32; 3]) -> u32 {
+        self.positions.extend_from_slice(&[x, y, z]);
+        self.normals.extend_from_slice(&normal);
+        (self.len() - 1) as u32
+    }
+}
+
+/// The base and top of a building in meters from `min_height` and `height`,
+/// or `building:levels` times `LEVEL_HEIGHT` when there is no height.
+pub fn heights(attributes: &Attributes) -> Option<(f32, f32)> {
+    let height = attributes.height.or(attributes.levels.map(|l| l * LEVEL_HEIGHT))?;
+    Some((attributes.min_height.unwrap_or(0.0), height))
+}
+
+/// Extrude `area` from `min_height` to `height`: a roof from its `cells` and
+/// a wall along each of its `rings()`. `x` and `y` are the area's positions
+/// and `z` is in meters, so scale `z` to match when positions are lon/lat or
+/// Mercator. Walls get their own vertices so each has a flat normal.
+pub fn extrude(area: &Area, min_height: f32, height: f32) -> Mesh {
+    let p = &area.positions;
+    let mut mesh = Mesh::default();
+    let up = [0.0, 0.0, 1.0];
+    for i in 0..p.len() / 2 {
+        mesh.vertex(p[i*2], p[i*2+1], height, up);
+    }
+    for c in area.cells.chunks_exact(3) {
+        if c.iter().any(|i| i*2+1 >= p.len()) { continue }
+        let tri = [
+            p[c[0]*2], p[c[0]*2+1], p[c[1]*2], p[c[1]*2+1], p[c[2]*2], p[c[2]*2+1],
+        ];
+        let (a,b,d) = (c[0] as u32, c[1] as u32, c[2] as u32);
+        if winding::signed_area(&tri) < 0.0 {
+            mesh.cells.extend_from_slice(&[a, d, b]);
+        } else {
+            mesh.cells.extend_from_slice(&[a, b, d]);
+        }
+    }
+    // rings keep the inside on their left, so walls face right
+    for ring in area.rings() {
+        for (k,&i) in ring.iter().enumerate() {
+            let j = ring[(k + 1) % ring.len()];
+            let (ax, ay, bx, by) = (p[i*2], p[i*2+1], p[j*2], p[j*2+1]);
+            let (dx, dy) = (bx - ax, by - ay);
+            let len = (dx * dx + dy * dy).sqrt();
+            if len == 0.0 { continue }
+            let normal = [dy / len, -dx / len, 0.0];
+            let a0 = mesh.vertex(ax, ay, min_height, normal);
+            let b0 = mesh.vertex(bx, by, min_height, normal);
+            let b1 = mesh.vertex(bx, by, height, normal);
+            let a1 = mesh.vertex(ax, ay, height, normal);
+            mesh.cells.extend_from_slice(&[a0, b0, b1, a0, b1, a1]);
+        }
+    }
+    mesh
+}
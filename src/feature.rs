@@ -1,4 +1,4 @@
-use crate::{Point,Line,Area};
+use crate::{Point,Line,Area,Encode,attributes::EXTENDED};
 use desert::{FromBytesLE,ToBytesLE};
 
 #[derive(Debug,Clone,PartialEq)]
@@ -8,6 +8,16 @@ pub enum Feature {
     Area(Area)
 }
 
+impl Feature {
+    pub fn id(&self) -> u64 {
+        match self {
+            Self::Point(point) => point.id,
+            Self::Line(line) => line.id,
+            Self::Area(area) => area.id,
+        }
+    }
+}
+
 pub fn decode(buf: &[u8]) -> Result<Feature,failure::Error> {
     Ok(Feature::from_bytes_le(buf)?.1)
 }
@@ -15,7 +25,7 @@ pub fn decode(buf: &[u8]) -> Result<Feature,failure::Error> {
 impl FromBytesLE for Feature {
     fn from_bytes_le(buf: &[u8]) -> Result<(usize,Self),failure::Error> {
         if buf.is_empty() { failure::bail!["not enough bytes to decode feature"] }
-        Ok(match buf[0] {
+        Ok(match buf[0] & !EXTENDED {
             0x01 => {
                 let (s,point) = Point::from_bytes_le(buf)?;
                 (s,Feature::Point(point))
@@ -44,4 +54,24 @@ impl ToBytesLE for Feature {
             Self::Area(area) => area.to_bytes_le(),
         }
     }
+    fn write_bytes_le(&self, dst: &mut [u8]) -> Result<usize,failure::Error> {
+        self.write_to(dst)
+    }
+}
+
+impl Encode for Feature {
+    fn encoded_len(&self) -> usize {
+        match self {
+            Self::Point(point) => point.encoded_len(),
+            Self::Line(line) => line.encoded_len(),
+            Self::Area(area) => area.encoded_len(),
+        }
+    }
+    fn write_to(&self, buf: &mut [u8]) -> Result<usize,failure::Error> {
+        match self {
+            Self::Point(point) => point.write_to(buf),
+            Self::Line(line) => line.write_to(buf),
+            Self::Area(area) => area.write_to(buf),
+        }
+    }
 }
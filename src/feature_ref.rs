@@ -0,0 +1,380 @@
+use crate::varint;
+use crate::label;
+use crate::{Point,Line,Area,Feature,Attributes,LineFlags,attributes,attributes::EXTENDED};
+use desert::FromBytesLE;
+use failure::Error;
+
+#[test]
+fn peer_feature_ref() -> Result<(),Error> {
+    use desert::ToBytesLE;
+    let positions: Vec<f32> = vec![
+        31.1848, 29.89774,
+        31.184889, 29.898802,
+        31.184858, 29.89839,
+    ];
+    let tags = vec![("name", "Neu Broderstorf"), ("aerialway", "cable_car")];
+    let point = Point::from_tags(1831881213, (12.253938, 54.090065), &tags)?;
+    let bytes = point.to_bytes_le()?;
+    match FeatureRef::from_bytes_le(&bytes)? {
+        (s,FeatureRef::Point(p)) => {
+            assert_eq!(s, bytes.len());
+            assert_eq!(p.id, point.id);
+            assert_eq!(p.feature_type, point.feature_type);
+            assert_eq!(p.point, point.point);
+            assert_eq!(p.labels, &point.labels[..]);
+        },
+        _ => panic!["expected point"],
+    }
+
+    let line = Line::from_tags(234941233, &[("highway", "residential")], &positions)?;
+    let bytes = line.to_bytes_le()?;
+    let (s,lref) = LineRef::from_bytes_le(&bytes)?;
+    assert_eq!(s, bytes.len());
+    assert_eq!(lref.positions().len(), 3);
+    assert_eq!(
+        lref.positions().flat_map(|(x,y)| [x,y]).collect::<Vec<f32>>(),
+        positions
+    );
+    assert_eq!(lref.into_owned()?, line);
+
+    let mut area = Area::from_tags(234941233, &[("leisure", "park")])?;
+    area.push(&positions, &[]);
+    let bytes = area.to_bytes_le()?;
+    let (s,aref) = AreaRef::from_bytes_le(&bytes)?;
+    assert_eq!(s, bytes.len());
+    assert_eq!(aref.cells().collect::<Vec<usize>>(), area.cells);
+    assert_eq!(decode_ref(&bytes)?.into_owned()?, Feature::Area(area));
+    Ok(())
+}
+
+#[test]
+fn truncated_feature_ref() -> Result<(),Error> {
+    use desert::ToBytesLE;
+    let tags = [("name", "Neu Broderstorf"), ("highway", "residential")];
+    let line = Line::from_tags(234941233, &tags, &[31.1848, 29.8977, 31.1849, 29.8988])?;
+    let mut area = Area::from_tags(234941233, &[("leisure", "park"), ("name", "Park")])?;
+    area.push(&[0.0, 0.0, 1.0, 0.0, 1.0, 1.0], &[]);
+    let point = Point::from_tags(1831881213, (12.253938, 54.090065), &tags)?;
+    for bytes in [point.to_bytes_le()?, line.to_bytes_le()?, area.to_bytes_le()?].iter() {
+        for n in 0..bytes.len() {
+            assert!(decode_ref(&bytes[0..n]).is_err(), "decoded {} of {} bytes", n, bytes.len());
+            assert!(crate::decode(&bytes[0..n]).is_err(), "decoded {} of {} bytes", n, bytes.len());
+        }
+    }
+    // counts far past the end of the record fail instead of allocating
+    let huge = [0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x7f];
+    let line = [&[0x02, 0x00, 0x00][..], &huge].concat();
+    let area = [&[0x03, 0x00, 0x00][..], &huge].concat();
+    let cells = [&[0x03, 0x00, 0x00, 0x01, 0, 0, 0, 0, 0, 0, 0, 0][..], &huge].concat();
+    for bytes in [line, area, cells].iter() {
+        assert!(decode_ref(bytes).is_err());
+        assert!(crate::decode(bytes).is_err());
+    }
+    Ok(())
+}
+
+/// Iterator over the `(lon,lat)` pairs of an encoded feature without copying
+/// them out of the source buffer.
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub struct Positions<'a> {
+    buf: &'a [u8],
+}
+
+impl<'a> Positions<'a> {
+    /// Raw little-endian f32 pairs, suitable for uploading as-is.
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.buf
+    }
+}
+
+impl<'a> Iterator for Positions<'a> {
+    type Item = (f32,f32);
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.buf.len() < 8 { return None }
+        let x = f32::from_le_bytes([self.buf[0],self.buf[1],self.buf[2],self.buf[3]]);
+        let y = f32::from_le_bytes([self.buf[4],self.buf[5],self.buf[6],self.buf[7]]);
+        self.buf = &self.buf[8..];
+        Some((x,y))
+    }
+    fn size_hint(&self) -> (usize,Option<usize>) {
+        let n = self.buf.len() / 8;
+        (n,Some(n))
+    }
+}
+
+impl<'a> ExactSizeIterator for Positions<'a> {}
+
+/// Iterator over the triangle indexes of an encoded area.
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub struct Cells<'a> {
+    buf: &'a [u8],
+    remaining: usize,
+}
+
+impl<'a> Iterator for Cells<'a> {
+    type Item = usize;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 { return None }
+        // bounds were checked when the view was created
+        let (s,c) = varint::decode(self.buf).ok()?;
+        self.buf = &self.buf[s..];
+        self.remaining -= 1;
+        Some(c as usize)
+    }
+    fn size_hint(&self) -> (usize,Option<usize>) {
+        (self.remaining,Some(self.remaining))
+    }
+}
+
+impl<'a> ExactSizeIterator for Cells<'a> {}
+
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub struct PointRef<'a> {
+    pub id: u64,
+    pub point: (f32, f32),
+    pub feature_type: u64,
+    pub labels: &'a [u8],
+    attributes: &'a [u8],
+}
+
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub struct LineRef<'a> {
+    pub id: u64,
+    pub feature_type: u64,
+    pub labels: &'a [u8],
+    attributes: &'a [u8],
+    positions: &'a [u8],
+}
+
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub struct AreaRef<'a> {
+    pub id: u64,
+    pub feature_type: u64,
+    pub labels: &'a [u8],
+    attributes: &'a [u8],
+    positions: &'a [u8],
+    cells: &'a [u8],
+    ccount: usize,
+}
+
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub enum FeatureRef<'a> {
+    Point(PointRef<'a>),
+    Line(LineRef<'a>),
+    Area(AreaRef<'a>),
+}
+
+pub fn decode_ref<'a>(buf: &'a [u8]) -> Result<FeatureRef<'a>,Error> {
+    Ok(FeatureRef::from_bytes_le(buf)?.1)
+}
+
+fn header(buf: &[u8], expected: u8, name: &str) -> Result<(usize,u64,u64),Error> {
+    if buf.is_empty() { failure::bail!["not enough bytes to decode {}", name] }
+    if buf[0] & !EXTENDED != expected {
+        failure::bail!["parsing {} failed. expected 0x{:02x}, received 0x{:02x}",
+            name, expected, buf[0]];
+    }
+    let mut offset = 1;
+    let (s,feature_type) = varint::decode(&buf[offset..])?;
+    offset += s;
+    let (s,id) = varint::decode(&buf[offset..])?;
+    offset += s;
+    Ok((offset,feature_type,id))
+}
+
+fn attributes(buf: &[u8], offset: usize) -> Result<(usize,&[u8]),Error> {
+    if buf[0] & EXTENDED == 0 { return Ok((offset,&[])) }
+    let s = attributes::scan(&buf[offset..])?;
+    Ok((offset+s,&buf[offset..offset+s]))
+}
+
+fn decode_attributes(buf: &[u8]) -> Result<Attributes,Error> {
+    if buf.is_empty() { return Ok(Attributes::default()) }
+    Ok(Attributes::from_bytes_le(buf)?.1)
+}
+
+fn positions_slice(buf: &[u8], offset: usize) -> Result<(usize,&[u8]),Error> {
+    let (s,pcount) = varint::decode(&buf[offset..])?;
+    let start = offset + s;
+    let end = (pcount as usize).checked_mul(8)
+        .and_then(|n| n.checked_add(start))
+        .filter(|end| *end <= buf.len());
+    match end {
+        Some(end) => Ok((end,&buf[start..end])),
+        None => failure::bail!["not enough bytes to decode {} positions", pcount],
+    }
+}
+
+impl<'a> PointRef<'a> {
+    pub fn from_bytes_le(buf: &'a [u8]) -> Result<(usize,Self),Error> {
+        let (mut offset,feature_type,id) = header(buf, 0x01, "node")?;
+        let (s,lon) = f32::from_bytes_le(&buf[offset..])?;
+        offset += s;
+        let (s,lat) = f32::from_bytes_le(&buf[offset..])?;
+        offset += s;
+        let s = label::scan(&buf[offset..])?;
+        let labels = &buf[offset..offset+s];
+        let (offset,attributes) = attributes(buf, offset+s)?;
+        Ok((offset, Self { id, point: (lon,lat), feature_type, labels, attributes }))
+    }
+    /// Decode the attribute section, which is empty unless the record has
+    /// `EXTENDED` set on its type byte.
+    pub fn attributes(&self) -> Result<Attributes,Error> {
+        decode_attributes(self.attributes)
+    }
+    pub fn into_owned(self) -> Result<Point,Error> {
+        Ok(Point {
+            id: self.id,
+            point: self.point,
+            feature_type: self.feature_type,
+            labels: self.labels.to_vec(),
+            attributes: decode_attributes(self.attributes)?,
+        })
+    }
+}
+
+impl<'a> LineRef<'a> {
+    pub fn from_bytes_le(buf: &'a [u8]) -> Result<(usize,Self),Error> {
+        let (offset,feature_type,id) = header(buf, 0x02, "line")?;
+        let (offset,positions) = positions_slice(buf, offset)?;
+        let s = label::scan(&buf[offset..])?;
+        let labels = &buf[offset..offset+s];
+        let (offset,attributes) = attributes(buf, offset+s)?;
+        Ok((offset, Self { id, feature_type, labels, attributes, positions }))
+    }
+    pub fn positions(&self) -> Positions<'a> {
+        Positions { buf: self.positions }
+    }
+    /// Decode the attribute section, which is empty unless the record has
+    /// `EXTENDED` set on its type byte.
+    pub fn attributes(&self) -> Result<Attributes,Error> {
+        decode_attributes(self.attributes)
+    }
+    pub fn flags(&self) -> Result<LineFlags,Error> {
+        Ok(LineFlags::from_bits(self.attributes()?.flags))
+    }
+    pub fn into_owned(self) -> Result<Line,Error> {
+        let mut attributes = decode_attributes(self.attributes)?;
+        let flags = LineFlags::from_bits(attributes.flags);
+        attributes.flags = 0;
+        Ok(Line {
+            id: self.id,
+            feature_type: self.feature_type,
+            labels: self.labels.to_vec(),
+            positions: self.positions().flat_map(|(x,y)| [x,y]).collect(),
+            attributes,
+            flags,
+        })
+    }
+}
+
+impl<'a> AreaRef<'a> {
+    pub fn from_bytes_le(buf: &'a [u8]) -> Result<(usize,Self),Error> {
+        let (offset,feature_type,id) = header(buf, 0x03, "area")?;
+        let (mut offset,positions) = positions_slice(buf, offset)?;
+        let (s,ccount) = varint::decode(&buf[offset..])?;
+        offset += s;
+        let cstart = offset;
+        // each index takes at least one byte
+        let ccount = (ccount as usize).checked_mul(3)
+            .filter(|n| *n <= buf.len() - offset)
+            .ok_or_else(|| failure::format_err!["not enough bytes to decode {} cells", ccount])?;
+        for _ in 0..ccount {
+            let (s,_) = varint::decode(&buf[offset..])?;
+            offset += s;
+        }
+        let cells = &buf[cstart..offset];
+        let s = label::scan(&buf[offset..])?;
+        let labels = &buf[offset..offset+s];
+        let (offset,attributes) = attributes(buf, offset+s)?;
+        Ok((offset, Self { id, feature_type, labels, attributes, positions, cells, ccount }))
+    }
+    pub fn positions(&self) -> Positions<'a> {
+        Positions { buf: self.positions }
+    }
+    pub fn cells(&self) -> Cells<'a> {
+        Cells { buf: self.cells, remaining: self.ccount }
+    }
+    /// Decode the attribute section, which is empty unless the record has
+    /// `EXTENDED` set on its type byte.
+    pub fn attributes(&self) -> Result<Attributes,Error> {
+        decode_attributes(self.attributes)
+    }
+    pub fn into_owned(self) -> Result<Area,Error> {
+        Ok(Area {
+            id: self.id,
+            feature_type: self.feature_type,
+            labels: self.labels.to_vec(),
+            positions: self.positions().flat_map(|(x,y)| [x,y]).collect(),
+            cells: self.cells().collect(),
+            attributes: decode_attributes(self.attributes)?,
+        })
+    }
+}
+
+impl<'a> FeatureRef<'a> {
+    pub fn from_bytes_le(buf: &'a [u8]) -> Result<(usize,Self),Error> {
+        if buf.is_empty() { failure::bail!["not enough bytes to decode feature"] }
+        Ok(match buf[0] & !EXTENDED {
+            0x01 => {
+                let (s,point) = PointRef::from_bytes_le(buf)?;
+                (s,FeatureRef::Point(point))
+            },
+            0x02 => {
+                let (s,line) = LineRef::from_bytes_le(buf)?;
+                (s,FeatureRef::Line(line))
+            },
+            0x03 => {
+                let (s,area) = AreaRef::from_bytes_le(buf)?;
+                (s,FeatureRef::Area(area))
+            },
+            x => {
+                failure::bail!["cannot decode feature type. expected 0x01, 0x02, or 0x03. \
+                    received {}", x]
+            }
+        })
+    }
+    pub fn id(&self) -> u64 {
+        match self {
+            Self::Point(p) => p.id,
+            Self::Line(l) => l.id,
+            Self::Area(a) => a.id,
+        }
+    }
+    pub fn feature_type(&self) -> u64 {
+        match self {
+            Self::Point(p) => p.feature_type,
+            Self::Line(l) => l.feature_type,
+            Self::Area(a) => a.feature_type,
+        }
+    }
+    pub fn labels(&self) -> &'a [u8] {
+        match self {
+            Self::Point(p) => p.labels,
+            Self::Line(l) => l.labels,
+            Self::Area(a) => a.labels,
+        }
+    }
+    pub fn attributes(&self) -> Result<Attributes,Error> {
+        match self {
+            Self::Point(p) => p.attributes(),
+            Self::Line(l) => l.attributes(),
+            Self::Area(a) => a.attributes(),
+        }
+    }
+    /// Where to place the feature's label: the point itself, or the anchor
+    /// stored in the attribute section of lines and areas, if any.
+    pub fn anchor(&self) -> Result<Option<(f32,f32)>,Error> {
+        match self {
+            Self::Point(p) => Ok(Some(p.point)),
+            _ => Ok(self.attributes()?.anchor),
+        }
+    }
+    pub fn into_owned(self) -> Result<Feature,Error> {
+        Ok(match self {
+            Self::Point(p) => Feature::Point(p.into_owned()?),
+            Self::Line(l) => Feature::Line(l.into_owned()?),
+            Self::Area(a) => Feature::Area(a.into_owned()?),
+        })
+    }
+}
@@ -1,11 +1,343 @@
 use crate::varint;
+use failure::Error;
+use regex::Regex;
+
+#[test]
+fn label_rules() -> Result<(),Error> {
+    let tags = vec![
+        ("name", "Main St"), ("name:de", "Hauptstraße"), ("alt_name", "High St"),
+        ("ref", "A1"), ("addr:housenumber", "12"), ("highway", "primary"),
+    ];
+    let labels = LabelRules::default().labels(&tags);
+    assert_eq!(labels, vec!["=Main St", "de=Hauptstraße", "=High St"]);
+
+    let rules = LabelRules::default()
+        .include(LabelRule::new("^ref$")?)
+        .include(LabelRule::new("^addr:housenumber$")?.rewrite("housenumber"))
+        .exclude("^alt_name$")?
+        .max_count(4);
+    assert_eq!(
+        rules.labels(&tags),
+        vec!["=Main St", "de=Hauptstraße", "ref=A1", "housenumber=12"]
+    );
+    let bytes = rules.encode(&tags)?;
+    assert_eq!(scan(&bytes)?, bytes.len());
+    assert!(scan(&bytes[0..5]).is_err());
+    assert_eq!(&bytes[0..9], b"\x08=Main St");
+    assert_eq!(LabelRules::new().encode(&tags)?, vec![0]);
+    // every match is rewritten, not just the first
+    let rules = LabelRules::new().include(LabelRule::new("_")?.rewrite("-"));
+    assert_eq!(rules.labels(&[("old_alt_name", "x")]), vec!["old-alt-name=x"]);
+    Ok(())
+}
+
+#[test]
+fn language_preference() -> Result<(),Error> {
+    let tags = vec![
+        ("name:fr", "Allemagne"), ("name", "Deutschland"), ("name:en", "Germany"),
+        ("name:de", "Deutschland"), ("name:ja", "ドイツ"),
+    ];
+    let rules = LabelRules::default().languages(&["en", "de"]);
+    assert_eq!(rules.labels(&tags), vec!["=Deutschland", "en=Germany", "de=Deutschland"]);
+    let bytes = rules.encode(&tags)?;
+    assert_eq!(pick(&bytes, &["de-AT", "en"])?, Some("Germany"));
+    assert_eq!(pick(&bytes, &["fr"])?, Some("Deutschland"));
+    assert_eq!(pick(&encode(&["ja=ドイツ"])?, &["fr"])?, None);
+    let all = LabelRules::default().encode(&tags)?;
+    assert_eq!(pick(&all, &["ja", "en"])?, Some("ドイツ"));
+    assert_eq!(iter(&all).count(), 5);
+
+    // other names with a language are filtered on the language too, and the
+    // plain name is what `pick` falls back to whatever the tag order
+    let tags = vec![
+        ("alt_name", "BRD"), ("alt_name:fr", "RFA"), ("old_name:de", "Westdeutschland"),
+        ("name", "Deutschland"),
+    ];
+    let rules = LabelRules::default().languages(&["de"]);
+    assert_eq!(rules.labels(&tags), vec!["=Deutschland", "=BRD", "de=Westdeutschland"]);
+    assert_eq!(pick(&LabelRules::default().encode(&tags)?, &["it"])?, Some("Deutschland"));
+    Ok(())
+}
+
+#[test]
+fn label_length_and_validation() -> Result<(),Error> {
+    let rules = LabelRules::default().max_length(Some(5));
+    assert_eq!(rules.labels(&[("name", "Zürich"), ("name:de", "Zürich")]),
+        vec!["=Züri", "de=Züri"]);
+    // only the value is cut, and cutting "Zürich" at 2 bytes would split the ü
+    let rules = LabelRules::default().max_length(Some(2));
+    assert_eq!(rules.labels(&[("name:de", "Zürich")]), vec!["de=Z"]);
+    assert_eq!(decode(&rules.encode(&[("name:de", "Zürich")])?)?, vec![("de", "Z")]);
+    let long = "x".repeat(2000);
+    assert_eq!(LabelRules::default().labels(&[("name", long.as_str())])[0].len(),
+        DEFAULT_MAX_LENGTH + 1);
+    assert_eq!(LabelRules::default().max_length(None)
+        .labels(&[("name", long.as_str())])[0].len(), 2001);
+
+    let bad = vec![4, b'=', b'a', 0xc3, 0x28, 0];
+    assert_eq!(decode(&bad), Err(LabelError::InvalidUtf8 { offset: 0, valid_up_to: 2 }));
+    let mut buf = encode(&["=ok"])?;
+    buf.pop();
+    buf.extend_from_slice(&bad);
+    let mut labels = iter(&buf);
+    assert_eq!(labels.next(), Some(Ok(("", "ok"))));
+    assert_eq!(labels.next(), Some(Err(LabelError::InvalidUtf8 { offset: 4, valid_up_to: 2 })));
+    assert_eq!(labels.next(), None);
+    assert_eq!(decode(&[5, b'a']), Err(LabelError::Truncated { offset: 0 }));
+    Ok(())
+}
 
 pub fn scan(buf: &[u8]) -> Result<usize,failure::Error> {
     let mut offset = 0;
     loop {
         let (s,len) = varint::decode(&buf[offset..])?;
-        offset += s + (len as usize);
+        offset = (len as usize).checked_add(offset + s)
+            .filter(|end| *end <= buf.len())
+            .ok_or_else(|| failure::format_err!["not enough bytes to decode label"])?;
         if len == 0 { break }
     }
     Ok(offset)
 }
+
+/// Selects tags to keep as labels by matching their key against `pattern`.
+/// Kept tags are written as `key=value` where `key` has every match of
+/// `pattern` replaced with `rewrite` (which may use `$1`-style captures), or
+/// is left as-is when there is no rewrite.
+#[derive(Debug,Clone)]
+pub struct LabelRule {
+    pub pattern: Regex,
+    pub rewrite: Option<String>,
+}
+
+impl LabelRule {
+    pub fn new(pattern: &str) -> Result<Self,Error> {
+        Ok(Self { pattern: Regex::new(pattern)?, rewrite: None })
+    }
+    pub fn rewrite(mut self, rewrite: &str) -> Self {
+        self.rewrite = Some(rewrite.to_string());
+        self
+    }
+    fn apply(&self, key: &str) -> Option<String> {
+        if !self.pattern.is_match(key) { return None }
+        Some(match &self.rewrite {
+            Some(r) => self.pattern.replace_all(key, r.as_str()).to_string(),
+            None => key.to_string(),
+        })
+    }
+}
+
+/// Which tags are written into a feature's labels, and how.
+///
+/// A tag is kept if its key matches one of `include` and none of `exclude`,
+/// using the first matching include rule. Labels keep the order of the input
+/// tags, except that a plain `name` comes first, and stop after `max_count`
+/// when set.
+///
+/// The default keeps `name`, `name:xx` and `xx_name` tags with the `name`
+/// prefix stripped from the key, so `name:de=Berlin` becomes `de=Berlin`.
+#[derive(Debug,Clone)]
+pub struct LabelRules {
+    pub include: Vec<LabelRule>,
+    pub exclude: Vec<Regex>,
+    pub max_count: Option<usize>,
+    /// When set, only `name:xx` (and `alt_name:xx`-style) tags whose
+    /// rewritten key is a listed language are kept and they are ordered by
+    /// their position in this list, after all other labels.
+    pub languages: Option<Vec<String>>,
+    /// Values longer than this many bytes are truncated to the last whole
+    /// character that fits. Keys are never cut.
+    pub max_length: Option<usize>,
+}
+
+/// Default for `LabelRules::max_length`. OSM values are at most 255
+/// characters, so this only guards against malformed input.
+pub const DEFAULT_MAX_LENGTH: usize = 1024;
+
+impl Default for LabelRules {
+    fn default() -> Self {
+        Self::new().include(LabelRule::new("^(|[^:]+_)name($|:)").unwrap().rewrite(""))
+    }
+}
+
+impl LabelRules {
+    /// Rules that keep no labels at all.
+    pub fn new() -> Self {
+        Self {
+            include: vec![],
+            exclude: vec![],
+            max_count: None,
+            languages: None,
+            max_length: Some(DEFAULT_MAX_LENGTH),
+        }
+    }
+    pub fn include(mut self, rule: LabelRule) -> Self {
+        self.include.push(rule);
+        self
+    }
+    pub fn exclude(mut self, pattern: &str) -> Result<Self,Error> {
+        self.exclude.push(Regex::new(pattern)?);
+        Ok(self)
+    }
+    pub fn max_count(mut self, n: usize) -> Self {
+        self.max_count = Some(n);
+        self
+    }
+    pub fn max_length(mut self, n: Option<usize>) -> Self {
+        self.max_length = n;
+        self
+    }
+    pub fn languages(mut self, languages: &[&str]) -> Self {
+        self.languages = Some(languages.iter().map(|l| l.to_string()).collect());
+        self
+    }
+
+    /// The `key=value` label for `tag`, or `None` when it is not kept.
+    pub fn label(&self, tag: &(&str, &str)) -> Option<String> {
+        if self.exclude.iter().any(|re| re.is_match(tag.0)) { return None }
+        let key = self.include.iter().find_map(|rule| rule.apply(tag.0))?;
+        let value = match self.max_length {
+            Some(n) => &tag.1[..floor_char_boundary(tag.1, n)],
+            None => tag.1,
+        };
+        Some(key + "=" + value)
+    }
+
+    pub fn labels(&self, tags: &[(&str, &str)]) -> Vec<String> {
+        lazy_static! {
+            static ref LANGUAGE: Regex = Regex::new("^(|[^:]+_)name:").unwrap();
+        }
+        let mut ranked = tags.iter()
+            .filter_map(|tag| {
+                let label = self.label(tag)?;
+                // the plain name goes first so `pick` falls back to it rather
+                // than to another unprefixed name such as `alt_name`
+                let rank = match &self.languages {
+                    _ if tag.0 == "name" => 0,
+                    Some(languages) if LANGUAGE.is_match(tag.0) => {
+                        let lang = &label[..label.find('=')?];
+                        2 + languages.iter().position(|l| l == lang)?
+                    },
+                    _ => 1,
+                };
+                Some((rank, label))
+            })
+            .collect::<Vec<_>>();
+        ranked.sort_by_key(|(rank,_)| *rank);
+        let max_count = self.max_count.unwrap_or(usize::MAX);
+        ranked.into_iter().map(|(_,label)| label).take(max_count).collect()
+    }
+
+    /// Encode the labels for `tags` as varint length-prefixed strings
+    /// followed by a zero terminator.
+    pub fn encode(&self, tags: &[(&str, &str)]) -> Result<Vec<u8>,Error> {
+        encode(&self.labels(tags))
+    }
+}
+
+fn floor_char_boundary(s: &str, n: usize) -> usize {
+    if n >= s.len() { return s.len() }
+    (0..=n).rev().find(|i| s.is_char_boundary(*i)).unwrap_or(0)
+}
+
+#[derive(Debug,Clone,PartialEq)]
+pub enum LabelError {
+    Truncated { offset: usize },
+    InvalidUtf8 { offset: usize, valid_up_to: usize },
+}
+
+impl std::fmt::Display for LabelError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            LabelError::Truncated { offset } => {
+                write!(f, "not enough bytes to decode label at offset {}", offset)
+            },
+            LabelError::InvalidUtf8 { offset, valid_up_to } => {
+                write!(f, "invalid utf-8 in label at offset {} after {} valid bytes",
+                    offset, valid_up_to)
+            },
+        }
+    }
+}
+
+impl failure::Fail for LabelError {}
+
+/// Iterator over the `(key,value)` pairs of an encoded label buffer.
+#[derive(Debug,Clone)]
+pub struct Labels<'a> {
+    buf: &'a [u8],
+    offset: usize,
+    done: bool,
+}
+
+/// Iterate over the labels in `buf`, which may be followed by other data.
+pub fn iter<'a>(buf: &'a [u8]) -> Labels<'a> {
+    Labels { buf, offset: 0, done: false }
+}
+
+impl<'a> Labels<'a> {
+    fn next_label(&mut self) -> Result<Option<(&'a str, &'a str)>,LabelError> {
+        let offset = self.offset;
+        let buf = &self.buf[offset..];
+        let (s,len) = varint::decode(buf).map_err(|_| LabelError::Truncated { offset })?;
+        if len == 0 { return Ok(None) }
+        let end = (len as usize).checked_add(s)
+            .filter(|end| *end <= buf.len())
+            .ok_or(LabelError::Truncated { offset })?;
+        let label = std::str::from_utf8(&buf[s..end]).map_err(|e| {
+            LabelError::InvalidUtf8 { offset, valid_up_to: e.valid_up_to() }
+        })?;
+        self.offset += end;
+        Ok(Some(match label.find('=') {
+            Some(i) => (&label[..i], &label[i+1..]),
+            None => ("", label),
+        }))
+    }
+}
+
+impl<'a> Iterator for Labels<'a> {
+    type Item = Result<(&'a str, &'a str),LabelError>;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done { return None }
+        match self.next_label() {
+            Ok(Some(label)) => Some(Ok(label)),
+            Ok(None) => { self.done = true; None },
+            Err(e) => { self.done = true; Some(Err(e)) },
+        }
+    }
+}
+
+/// Decode every label in `buf`, validating that each one is UTF-8.
+pub fn decode(buf: &[u8]) -> Result<Vec<(&str, &str)>,LabelError> {
+    iter(buf).collect()
+}
+
+/// Pick the best label value for a language preference chain such as
+/// `["de-AT", "de", "en"]`, falling back to the default (unprefixed) name.
+pub fn pick<'a>(buf: &'a [u8], languages: &[&str]) -> Result<Option<&'a str>,LabelError> {
+    let labels = decode(buf)?;
+    for lang in languages.iter().chain(std::iter::once(&"")) {
+        if let Some((_,value)) = labels.iter().find(|(key,_)| key == lang) {
+            return Ok(Some(value));
+        }
+    }
+    Ok(None)
+}
+
+/// Encode `labels` as varint length-prefixed strings followed by a zero
+/// terminator.
+pub fn encode<S: AsRef<str>>(labels: &[S]) -> Result<Vec<u8>,Error> {
+    let len = labels.iter().fold(1, |acc, l| {
+        let n = l.as_ref().len();
+        acc + varint::length(n as u64) + n
+    });
+    let mut buf = vec![0u8; len];
+    let mut offset = 0;
+    for l in labels.iter() {
+        let bytes = l.as_ref().as_bytes();
+        offset += varint::encode(bytes.len() as u64, &mut buf[offset..])?;
+        buf[offset..offset+bytes.len()].copy_from_slice(bytes);
+        offset += bytes.len();
+    }
+    buf[offset] = 0x00;
+    Ok(buf)
+}
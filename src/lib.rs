@@ -1,4 +1,3 @@
-#![feature(drain_filter)]
 
 #[macro_use]
 extern crate lazy_static;
@@ -7,12 +6,33 @@ extern crate lazy_static;
 extern crate failure;
 
 pub mod varint;
+pub mod antimeridian;
 
+pub mod attributes;
+pub use attributes::Attributes;
+
+pub mod dictionary;
+pub use dictionary::Dictionary;
+
+pub mod batch;
+pub mod blocks;
+pub mod container;
 pub mod encode;
+pub mod extrude;
 pub mod label;
+#[cfg(feature = "mbtiles")]
+pub mod mbtiles;
+pub mod measure;
 pub mod osm_types;
+pub mod osm_xml;
+pub mod projection;
+pub use projection::Projection;
+pub mod stroke;
 pub mod tag_priorities;
+pub mod tiles;
 pub mod tags;
+pub mod triangulate;
+pub mod winding;
 
 mod node;
 pub use node::*;
@@ -23,8 +43,17 @@ pub use line::*;
 mod area;
 pub use area::*;
 
+mod area_builder;
+pub use area_builder::*;
+
 mod member;
 pub use member::*;
 
 mod feature;
 pub use feature::*;
+
+mod feature_ref;
+pub use feature_ref::*;
+
+mod write;
+pub use write::Encode;
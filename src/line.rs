@@ -1,15 +1,16 @@
-use crate::varint;
-use crate::{label, tags};
+use crate::{measure, projection, stroke, varint, Projection};
+use crate::{label, tags, Encode, Attributes, attributes::EXTENDED};
 use desert::{ToBytesLE, FromBytesLE};
 use failure::Error;
+use std::borrow::Cow;
 
 #[test]
 fn peer_line() -> Result<(),Error> {
     let tags = vec![("source", "bing"), ("highway", "residential")];
     let positions: Vec<f32> = vec![
-        31.184799400000003, 29.897739500000004,
-        31.184888100000002, 29.898801400000004,
-        31.184858400000003, 29.8983899,
+        31.1848, 29.89774,
+        31.184889, 29.898802,
+        31.184858, 29.89839,
     ];
     let id: u64 = 234941233;
     let line = Line::from_tags(id, &tags, &positions)?;
@@ -26,23 +27,171 @@ fn peer_line() -> Result<(),Error> {
     Ok(())
 }
 
+#[test]
+fn line_flags() -> Result<(),Error> {
+    let positions: Vec<f32> = vec![0.0, 0.0, 1.0, 1.0];
+    let tags = vec![
+        ("highway", "primary"), ("bridge", "viaduct"), ("layer", "2"), ("oneway", "-1"),
+    ];
+    let plain = Line::from_tags(1, &tags, &positions)?;
+    assert!(plain.flags.is_empty() && plain.attributes.is_empty());
+    assert_eq!(plain.to_bytes_le()?[0], 0x02);
+    let options = tags::Options { attributes: true, ..Default::default() };
+    let line = Line::from_tags_with(1, &tags, &positions, &options)?;
+    assert_eq!(line.flags, LineFlags { bridge: true, tunnel: false, oneway: Oneway::Backward });
+    assert_eq!(line.attributes.layer, Some(2));
+    let bytes = line.to_bytes_le()?;
+    assert_eq!(bytes[0], 0x12);
+    assert_eq!(Line::from_bytes_le(&bytes)?, (bytes.len(),line));
+
+    let tags = vec![("highway", "primary"), ("tunnel", "no"), ("junction", "roundabout")];
+    let flags = LineFlags::from_tags(&tags);
+    assert_eq!(flags, LineFlags { bridge: false, tunnel: false, oneway: Oneway::Forward });
+    assert_eq!(LineFlags::from_bits(flags.bits()), flags);
+    assert!(LineFlags::from_tags(&[("oneway", "no")]).is_empty());
+    Ok(())
+}
+
+#[test]
+fn line_length() {
+    let line = Line::new(1, 0, &[0], &[0.0, 0.0, 1.0, 0.0, 1.0, 1.0]);
+    let expected = measure::distance((0.0, 0.0), (1.0, 0.0))
+        + measure::distance((1.0, 0.0), (1.0, 1.0));
+    assert!((line.length_m() - expected).abs() < 1e-6);
+    assert!((line.length_m() - 222_390.16).abs() < 0.01, "{}", line.length_m());
+    assert_eq!(Line::new(1, 0, &[0], &[0.0, 0.0]).length_m(), 0.0);
+}
+
+#[test]
+fn line_anchor() -> Result<(),Error> {
+    use crate::decode_ref;
+    let mut line = Line::new(1, 0, &[0], &[0.0, 0.0, 2.0, 0.0]);
+    assert_eq!(line.anchor(), Some((1.0, 0.0)));
+    line.set_anchor();
+    let bytes = line.to_bytes_le()?;
+    assert_eq!(bytes[0], 0x12);
+    assert_eq!(decode_ref(&bytes)?.anchor()?, Some((1.0, 0.0)));
+    assert_eq!(Line::from_bytes_le(&bytes)?, (bytes.len(),line));
+    Ok(())
+}
+
+#[test]
+fn line_stroke() -> Result<(),Error> {
+    let line = Line::new(1, 0, &[0], &[0.0, 0.0, 1.0, 0.0, 1.0, 1.0]);
+    let options = stroke::StrokeOptions::default();
+    let stroke = line.stroke(&options);
+    assert_eq!(stroke.len(), 2*4 + 4);
+    let bytes = line.to_bytes_le()?;
+    assert_eq!(Line::from_bytes_le(&bytes)?.1.stroke(&options), stroke);
+    Ok(())
+}
+
+#[derive(Debug,Clone,Copy,PartialEq,Default)]
+pub enum Oneway {
+    #[default]
+    No,
+    /// traffic follows the order of the positions
+    Forward,
+    /// traffic runs against the order of the positions (`oneway=-1`)
+    Backward,
+}
+
+/// Road properties derived from `bridge`, `tunnel`, `oneway` and
+/// `junction=roundabout` tags.
+#[derive(Debug,Clone,Copy,Default,PartialEq)]
+pub struct LineFlags {
+    pub bridge: bool,
+    pub tunnel: bool,
+    pub oneway: Oneway,
+}
+
+const BRIDGE: u64 = 0x01;
+const TUNNEL: u64 = 0x02;
+const ONEWAY: u64 = 0x04;
+const ONEWAY_BACKWARD: u64 = 0x08;
+
+impl LineFlags {
+    pub fn from_tags(tags: &[(&str, &str)]) -> Self {
+        let mut flags = Self::default();
+        for (k,v) in tags.iter() {
+            match (*k,*v) {
+                ("bridge", "no") | ("tunnel", "no") => {},
+                ("bridge", _) => flags.bridge = true,
+                ("tunnel", _) => flags.tunnel = true,
+                ("oneway", "yes") | ("oneway", "true") | ("oneway", "1") => {
+                    flags.oneway = Oneway::Forward;
+                },
+                ("oneway", "-1") | ("oneway", "reverse") => flags.oneway = Oneway::Backward,
+                ("junction", "roundabout") if flags.oneway == Oneway::No => {
+                    flags.oneway = Oneway::Forward;
+                },
+                _ => {},
+            }
+        }
+        flags
+    }
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+    pub fn bits(&self) -> u64 {
+        (if self.bridge { BRIDGE } else { 0 })
+            | (if self.tunnel { TUNNEL } else { 0 })
+            | match self.oneway {
+                Oneway::No => 0,
+                Oneway::Forward => ONEWAY,
+                Oneway::Backward => ONEWAY | ONEWAY_BACKWARD,
+            }
+    }
+    pub fn from_bits(bits: u64) -> Self {
+        Self {
+            bridge: bits & BRIDGE != 0,
+            tunnel: bits & TUNNEL != 0,
+            oneway: match (bits & ONEWAY != 0, bits & ONEWAY_BACKWARD != 0) {
+                (false,_) => Oneway::No,
+                (true,false) => Oneway::Forward,
+                (true,true) => Oneway::Backward,
+            },
+        }
+    }
+}
+
+/// A polyline. `flags` and attributes such as `layer` are opt-in through
+/// `tags::Options::attributes`. Lines with flags or attributes are written as
+/// extended records with the flags stored in the attribute section.
 #[derive(Debug,Clone,PartialEq)]
 pub struct Line {
     pub id: u64,
     pub positions: Vec<f32>,
     pub feature_type: u64,
     pub labels: Vec<u8>,
+    pub attributes: Attributes,
+    pub flags: LineFlags,
 }
 
 impl Line {
     pub fn from_tags(id: u64, tags: &[(&str, &str)], positions: &[f32]) -> Result<Line,Error> {
         let (feature_type, labels) = tags::parse(tags)?;
-        Ok(Line {
-            id,
-            positions: positions.to_vec(),
-            feature_type,
-            labels
-        })
+        Ok(Line::new(id, feature_type, &labels, positions))
+    }
+    pub fn from_tags_with(
+        id: u64,
+        tags: &[(&str, &str)],
+        positions: &[f32],
+        options: &tags::Options,
+    ) -> Result<Line,Error> {
+        let (feature_type, labels) = tags::parse_with(tags, options)?;
+        let mut line = Line::new(id, feature_type, &labels, positions);
+        line.apply_options(tags, options);
+        Ok(line)
+    }
+    // set attributes and flags from lon/lat `tags` as `options` asks for and
+    // convert the positions to `options.projection`
+    pub(crate) fn apply_options(&mut self, tags: &[(&str, &str)], options: &tags::Options) {
+        self.attributes = options.attributes(tags);
+        if options.attributes {
+            self.flags = LineFlags::from_tags(tags);
+        }
+        projection::project(&mut self.positions, Projection::LonLat, options.projection);
     }
     pub fn new(id: u64, feature_type: u64, labels: &[u8], positions: &[f32]) -> Line {
         Line {
@@ -50,21 +199,63 @@ impl Line {
             feature_type,
             labels: labels.to_vec(),
             positions: positions.to_vec(),
+            attributes: Attributes::default(),
+            flags: LineFlags::default(),
         }
     }
+    /// Great-circle length of the line in meters.
+    pub fn length_m(&self) -> f64 {
+        measure::length(&projection::lonlat(&self.positions, self.attributes.projection))
+    }
+    /// The point halfway along the line, where a label would be centered.
+    pub fn anchor(&self) -> Option<(f32,f32)> {
+        let projection = self.attributes.projection;
+        measure::midpoint(&projection::lonlat(&self.positions, projection))
+            .map(|p| projection::convert(p, Projection::LonLat, projection))
+    }
+    /// Tessellate the line into triangles for drawing. See `stroke::tessellate`.
+    pub fn stroke(&self, options: &stroke::StrokeOptions) -> stroke::Stroke {
+        stroke::tessellate(&self.positions, options)
+    }
+    /// Convert the positions and anchor to `to` and record it in the
+    /// attribute section.
+    pub fn project(&mut self, to: Projection) {
+        let from = self.attributes.projection;
+        projection::project(&mut self.positions, from, to);
+        self.attributes.anchor = self.attributes.anchor
+            .map(|p| projection::convert(p, from, to));
+        self.attributes.projection = to;
+    }
+    /// Store `anchor()` in the attribute section so clients can place the
+    /// label without decoding the positions.
+    pub fn set_anchor(&mut self) {
+        self.attributes.anchor = self.anchor();
+    }
+    // the attribute section as written, with flags folded in
+    fn section(&self) -> Cow<'_, Attributes> {
+        if self.flags.is_empty() { return Cow::Borrowed(&self.attributes) }
+        let mut attrs = self.attributes.clone();
+        attrs.flags = self.flags.bits();
+        Cow::Owned(attrs)
+    }
 }
 
-impl ToBytesLE for Line {
-    fn to_bytes_le(&self) -> Result<Vec<u8>, Error> {
+impl Encode for Line {
+    fn encoded_len(&self) -> usize {
         let pcount = self.positions.len()/2;
-        let ft_length = varint::length(self.feature_type);
-        let id_length = varint::length(self.id);
-        let pcount_length = varint::length(pcount as u64);
-
-        let mut buf =
-            vec![0u8; 1 + ft_length + id_length + pcount_length + (2 * 4 * pcount) + self.labels.len()];
+        let section = self.section();
+        1 + varint::length(self.feature_type) + varint::length(self.id)
+            + varint::length(pcount as u64) + (2 * 4 * pcount) + self.labels.len()
+            + if section.is_empty() { 0 } else { section.encoded_len() }
+    }
+    fn write_to(&self, buf: &mut [u8]) -> Result<usize, Error> {
+        if buf.len() < self.encoded_len() {
+            failure::bail!["buffer is too small to write line"];
+        }
+        let pcount = self.positions.len()/2;
+        let section = self.section();
         let mut offset = 0;
-        buf[offset] = 0x02;
+        buf[offset] = if section.is_empty() { 0x02 } else { 0x02 | EXTENDED };
         offset += 1;
 
         offset += varint::encode(self.feature_type, &mut buf[offset..])?;
@@ -77,14 +268,29 @@ impl ToBytesLE for Line {
             offset += p.write_bytes_le(&mut buf[offset..])?;
         }
 
-        buf[offset..].copy_from_slice(&self.labels);
-        return Ok(buf);
+        buf[offset..offset+self.labels.len()].copy_from_slice(&self.labels);
+        offset += self.labels.len();
+        if !section.is_empty() {
+            offset += section.write_to(&mut buf[offset..])?;
+        }
+        Ok(offset)
+    }
+}
+
+impl ToBytesLE for Line {
+    fn to_bytes_le(&self) -> Result<Vec<u8>, Error> {
+        let mut buf = vec![0u8; self.encoded_len()];
+        self.write_to(&mut buf)?;
+        Ok(buf)
+    }
+    fn write_bytes_le(&self, dst: &mut [u8]) -> Result<usize, Error> {
+        self.write_to(dst)
     }
 }
 
 impl FromBytesLE for Line {
     fn from_bytes_le(buf: &[u8]) -> Result<(usize,Self), Error> {
-        if buf[0] != 0x02 {
+        if buf[0] & !EXTENDED != 0x02 {
             failure::bail!["parsing line failed. expected 0x02, received 0x{:02x}", buf[0]];
         }
         let mut offset = 1;
@@ -95,6 +301,10 @@ impl FromBytesLE for Line {
 
         let (s,pcount) = varint::decode(&buf[offset..])?;
         offset += s;
+        // check the count against the bytes left before allocating for it
+        if (pcount as usize).checked_mul(8).filter(|n| *n <= buf.len() - offset).is_none() {
+            failure::bail!["not enough bytes to decode {} positions", pcount];
+        }
 
         let mut positions = Vec::with_capacity((pcount as usize)*2);
         for _ in 0..pcount*2 {
@@ -106,6 +316,14 @@ impl FromBytesLE for Line {
         let s = label::scan(&buf[offset..])?;
         let labels = buf[offset..offset+s].to_vec();
         offset += s;
-        Ok((offset, Self { id, positions, feature_type, labels }))
+        let mut attributes = Attributes::default();
+        if buf[0] & EXTENDED != 0 {
+            let (s,attrs) = Attributes::from_bytes_le(&buf[offset..])?;
+            offset += s;
+            attributes = attrs;
+        }
+        let flags = LineFlags::from_bits(attributes.flags);
+        attributes.flags = 0;
+        Ok((offset, Self { id, positions, feature_type, labels, attributes, flags }))
     }
 }
@@ -0,0 +1,243 @@
+use crate::container::{type_table, types_hash, VERSION};
+use crate::tiles::{decode_tile, Manifest, Tile, TileSink, Tiler};
+use crate::Feature;
+use failure::Error;
+use rusqlite::{params, Connection, OpenFlags, OptionalExtension};
+use std::path::Path;
+
+#[test]
+fn mbtiles_round_trip() -> Result<(),Error> {
+    use crate::tiles::TilerOptions;
+    use crate::{Line, Point};
+    use desert::ToBytesLE;
+    let mut tiler = Tiler::new(TilerOptions { min_zoom: 0, max_zoom: 2, clip: true, ..Default::default() });
+    let point = Point::from_tags(1, (10.0, 10.0), &[("amenity", "cafe")])?;
+    tiler.add(&point.to_bytes_le()?)?;
+    let line = Line::from_tags(2, &[("highway", "primary")], &[-10.0, 10.0, 10.0, 10.0])?;
+    tiler.add(&line.to_bytes_le()?)?;
+
+    let path = std::env::temp_dir().join(format!("georender-{}.mbtiles", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let mut writer = Writer::create(&path)?;
+    writer.write_tiler(&tiler)?;
+    writer.set_metadata("name", "test")?;
+    writer.finish()?;
+
+    assert!(Writer::create(&path).is_err());
+    let reader = Reader::open(&path)?;
+    assert_eq!(reader.metadata("name")?, Some("test".to_string()));
+    assert_eq!(reader.metadata(VERSION_KEY)?, Some(VERSION.to_string()));
+    assert_eq!(reader.metadata("version")?, None);
+    assert!(reader.conn.execute("DELETE FROM metadata", params![]).is_err());
+    assert_eq!(reader.metadata("minzoom")?, Some("0".to_string()));
+    assert_eq!(reader.metadata("maxzoom")?, Some("2".to_string()));
+    for (tile,buf) in tiler.tiles() {
+        assert_eq!(reader.tile(tile)?.as_deref(), Some(buf));
+    }
+    // tile_row counts up from the south edge
+    let row: u32 = reader.conn.query_row(
+        "SELECT tile_row FROM tiles WHERE zoom_level = 2 AND tile_column = 2",
+        params![], |r| r.get(0)
+    )?;
+    assert_eq!(row, 2);
+    assert_eq!(reader.features(&Tile { z: 2, x: 2, y: 1 })?.len(), 2);
+    assert_eq!(reader.tile(&Tile { z: 2, x: 0, y: 0 })?, None);
+    assert_eq!(reader.tile(&Tile { z: 2, x: 0, y: 4 })?, None);
+    assert_eq!(reader.tile(&Tile { z: 64, x: 0, y: 0 })?, None);
+    assert!(reader.features(&Tile { z: 1, x: 2, y: 0 })?.is_empty());
+    assert!(Reader::open_with_types(&path, &["amenity.cafe"]).is_err());
+    std::fs::remove_file(&path)?;
+    // opening a missing database doesn't create it
+    assert!(Reader::open(&path).is_err());
+    assert!(!path.exists());
+
+    // flushing twice appends to tiles already written
+    let mut writer = Writer::create(&path)?;
+    assert!(writer.write_tile(&Tile { z: 0, x: 1, y: 0 }, &[]).is_err());
+    assert!(writer.append(&Tile { z: 40, x: 0, y: 0 }, &[]).is_err());
+    let mut tiler = Tiler::new(TilerOptions { min_zoom: 0, max_zoom: 0, ..Default::default() });
+    tiler.add(&point.to_bytes_le()?)?;
+    tiler.flush(&mut writer)?;
+    tiler.add(&line.to_bytes_le()?)?;
+    tiler.flush(&mut writer)?;
+    writer.write_manifest(&tiler.manifest())?;
+    writer.finish()?;
+    let reader = Reader::open(&path)?;
+    assert_eq!(reader.features(&Tile { z: 0, x: 0, y: 0 })?, vec![
+        Feature::Point(point), Feature::Line(line),
+    ]);
+    assert_eq!(reader.metadata("maxzoom")?, Some("0".to_string()));
+    std::fs::remove_file(&path)?;
+    Ok(())
+}
+
+/// Value of the `format` metadata entry.
+pub const FORMAT: &str = "georender";
+/// Metadata entry holding the pack `VERSION`. MBTiles reserves `version` for
+/// the tileset's own version.
+pub const VERSION_KEY: &str = "georender_version";
+
+/// Writes tiles into a SQLite database with the MBTiles schema: a `tiles`
+/// table of `zoom_level`, `tile_column`, `tile_row` and `tile_data`, with
+/// rows counted up from the south as in TMS, and a `metadata` table of names
+/// and values. `tile_data` holds a tile's concatenated records. Besides the
+/// usual entries, the metadata records the pack version as
+/// `georender_version` and the
+/// `types_hash` of the type table the records were encoded with. Everything
+/// is written in one transaction, committed by `finish`.
+pub struct Writer {
+    conn: Connection,
+}
+
+impl Writer {
+    /// Create the database and its tables at `path`, which must not exist.
+    pub fn create<P: AsRef<Path>>(path: P) -> Result<Self,Error> {
+        let path = path.as_ref();
+        if path.exists() {
+            failure::bail!["{} already exists", path.display()];
+        }
+        let flags = OpenFlags::SQLITE_OPEN_READ_WRITE | OpenFlags::SQLITE_OPEN_CREATE;
+        let conn = Connection::open_with_flags(path, flags)?;
+        conn.execute_batch("
+            CREATE TABLE IF NOT EXISTS metadata (name TEXT, value TEXT);
+            CREATE UNIQUE INDEX IF NOT EXISTS name ON metadata (name);
+            CREATE TABLE IF NOT EXISTS tiles (
+                zoom_level INTEGER, tile_column INTEGER, tile_row INTEGER, tile_data BLOB
+            );
+            CREATE UNIQUE INDEX IF NOT EXISTS tile_index ON tiles (zoom_level, tile_column, tile_row);
+            BEGIN;
+        ")?;
+        let mut writer = Self { conn };
+        writer.set_metadata("format", FORMAT)?;
+        writer.set_metadata(VERSION_KEY, &VERSION.to_string())?;
+        writer.set_metadata("types_hash", &format!("{:016x}", types_hash(&type_table())))?;
+        Ok(writer)
+    }
+    pub fn set_metadata(&mut self, name: &str, value: &str) -> Result<(),Error> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO metadata (name, value) VALUES (?1, ?2)",
+            params![name, value]
+        )?;
+        Ok(())
+    }
+    /// Write the concatenated records of a tile, replacing any already there.
+    /// Tiles outside the zoom's grid are an error.
+    pub fn write_tile(&mut self, tile: &Tile, data: &[u8]) -> Result<(),Error> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO tiles (zoom_level, tile_column, tile_row, tile_data) \
+                VALUES (?1, ?2, ?3, ?4)",
+            params![tile.z, tile.x, tms_row(tile)?, data]
+        )?;
+        Ok(())
+    }
+    /// Write every buffered tile of `tiler` along with its zoom range and
+    /// bounds. To stream, `flush` the tiler into this writer as it fills up
+    /// and call `write_manifest` at the end instead.
+    pub fn write_tiler(&mut self, tiler: &Tiler) -> Result<(),Error> {
+        for (tile,data) in tiler.tiles() {
+            self.write_tile(tile, data)?;
+        }
+        self.write_manifest(&tiler.manifest())
+    }
+    /// Record the zoom range and bounds of the tiles in `manifest`.
+    pub fn write_manifest(&mut self, manifest: &Manifest) -> Result<(),Error> {
+        self.set_metadata("minzoom", &manifest.min_zoom.to_string())?;
+        self.set_metadata("maxzoom", &manifest.max_zoom.to_string())?;
+        let mut bbox = crate::blocks::BBox::default();
+        for entry in manifest.tiles.iter() {
+            bbox.union(&entry.tile.bbox());
+        }
+        if !bbox.is_empty() {
+            self.set_metadata("bounds", &format!(
+                "{},{},{},{}", bbox.min_x, bbox.min_y, bbox.max_x, bbox.max_y
+            ))?;
+        }
+        Ok(())
+    }
+    /// Commit everything written.
+    pub fn finish(self) -> Result<(),Error> {
+        self.conn.execute_batch("COMMIT;")?;
+        Ok(())
+    }
+}
+
+impl TileSink for Writer {
+    fn append(&mut self, tile: &Tile, data: &[u8]) -> Result<(),Error> {
+        self.conn.execute(
+            "INSERT INTO tiles (zoom_level, tile_column, tile_row, tile_data) \
+                VALUES (?1, ?2, ?3, ?4) \
+                ON CONFLICT (zoom_level, tile_column, tile_row) \
+                DO UPDATE SET tile_data = CAST(tile_data || excluded.tile_data AS BLOB)",
+            params![tile.z, tile.x, tms_row(tile)?, data]
+        )?;
+        Ok(())
+    }
+}
+
+/// Fetches tiles from a database written by `Writer`, refusing databases
+/// written with a different type table.
+pub struct Reader {
+    conn: Connection,
+}
+
+impl Reader {
+    /// Open a database written against the built-in type table.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self,Error> {
+        Self::open_with_types(path, &type_table())
+    }
+    /// Open a database written against `types`, listed in id order.
+    pub fn open_with_types<P: AsRef<Path>, S: AsRef<str>>(path: P, types: &[S]) -> Result<Self,Error> {
+        let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+        let reader = Self { conn };
+        if reader.metadata("format")?.as_deref() != Some(FORMAT) {
+            failure::bail!["not a georender tile database"];
+        }
+        if let Some(version) = reader.metadata(VERSION_KEY)? {
+            if version.parse::<u8>()? > VERSION {
+                failure::bail!["unsupported pack version {}, expected at most {}", version, VERSION];
+            }
+        }
+        let expected = format!("{:016x}", types_hash(types));
+        match reader.metadata("types_hash")? {
+            Some(hash) if hash == expected => {},
+            hash => failure::bail![
+                "tiles were written with a different type table ({}, expected {})",
+                hash.unwrap_or_else(|| "none".to_string()), expected
+            ],
+        }
+        Ok(reader)
+    }
+    pub fn metadata(&self, name: &str) -> Result<Option<String>,Error> {
+        Ok(self.conn.query_row(
+            "SELECT value FROM metadata WHERE name = ?1",
+            params![name], |row| row.get(0)
+        ).optional()?)
+    }
+    /// The concatenated records of a tile, if it was written.
+    /// Tiles outside the zoom's grid are never written.
+    pub fn tile(&self, tile: &Tile) -> Result<Option<Vec<u8>>,Error> {
+        let row = match tms_row(tile) {
+            Ok(row) => row,
+            Err(_) => return Ok(None),
+        };
+        Ok(self.conn.query_row(
+            "SELECT tile_data FROM tiles \
+                WHERE zoom_level = ?1 AND tile_column = ?2 AND tile_row = ?3",
+            params![tile.z, tile.x, row], |row| row.get(0)
+        ).optional()?)
+    }
+    /// Decode the records of a tile. Missing tiles have no features.
+    pub fn features(&self, tile: &Tile) -> Result<Vec<Feature>,Error> {
+        match self.tile(tile)? {
+            Some(data) => decode_tile(&data),
+            None => Ok(vec![]),
+        }
+    }
+}
+
+fn tms_row(tile: &Tile) -> Result<u32,Error> {
+    if tile.z >= 32 || (tile.x as u64) >> tile.z != 0 || (tile.y as u64) >> tile.z != 0 {
+        failure::bail!["tile {}/{}/{} is out of range", tile.z, tile.x, tile.y];
+    }
+    Ok(((1u64 << tile.z) - 1 - tile.y as u64) as u32)
+}
@@ -0,0 +1,261 @@
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+#[test]
+fn haversine_distance() {
+    // one degree of longitude along the equator
+    let d = distance((0.0, 0.0), (1.0, 0.0));
+    assert!((d - 111_195.08).abs() < 0.01, "{}", d);
+    // paris to london
+    let d = distance((2.3522, 48.8566), (-0.1276, 51.5072));
+    assert!((d - 343_556.0).abs() < 500.0, "{}", d);
+}
+
+#[test]
+fn spherical_triangle_area() {
+    // one octant of the sphere
+    let a = triangle_area((0.0, 0.0), (90.0, 0.0), (0.0, 90.0));
+    let octant = 4.0 * std::f64::consts::PI * EARTH_RADIUS * EARTH_RADIUS / 8.0;
+    assert!((a - octant).abs() / octant < 1e-9);
+    // a small triangle near the equator is close to its planar area
+    let a = triangle_area((0.0, 0.0), (0.001, 0.0), (0.0, 0.001));
+    let side = distance((0.0, 0.0), (0.001, 0.0));
+    assert!((a - side * side / 2.0).abs() < 0.001, "{}", a);
+}
+
+#[test]
+fn pole_of_l_shape() {
+    // the pole of an L shape lies in its wide corner, not at the centroid
+    let positions: Vec<f32> = vec![
+        0.0, 0.0, 10.0, 0.0, 10.0, 2.0, 2.0, 2.0, 2.0, 10.0, 0.0, 10.0,
+    ];
+    let rings = vec![vec![0, 1, 2, 3, 4, 5]];
+    let (x, y) = pole_of_inaccessibility(&positions, &rings, 0.01).unwrap();
+    assert!((0.0..=2.0).contains(&x) && (0.0..=2.0).contains(&y), "{} {}", x, y);
+    assert_eq!(pole_of_inaccessibility(&positions, &[], 0.01), None);
+}
+
+#[test]
+fn pole_of_thin_area() {
+    let positions: Vec<f32> = vec![0.0, 0.0, 10.0, 0.0, 10.0, 0.001, 0.0, 0.001];
+    let rings = vec![vec![0, 1, 2, 3]];
+    let (x, y) = pole_of_inaccessibility(&positions, &rings, 0.0001).unwrap();
+    assert!(x > 0.0 && x < 10.0 && y > 0.0 && y < 0.001, "{} {}", x, y);
+}
+
+#[test]
+fn path_midpoint() {
+    let positions: Vec<f32> = vec![0.0, 0.0, 1.0, 0.0, 1.0, 1.0, 1.0, 3.0];
+    let (x, y) = midpoint(&positions).unwrap();
+    assert_eq!(x, 1.0);
+    assert!((y - 1.0).abs() < 1e-3, "{}", y);
+    assert_eq!(midpoint(&[2.0, 3.0]), Some((2.0, 3.0)));
+    assert_eq!(midpoint(&[]), None);
+}
+
+/// Mean radius of the earth in meters.
+pub const EARTH_RADIUS: f64 = 6_371_008.8;
+
+/// Great-circle distance in meters between two lon/lat points in degrees,
+/// using the haversine formula.
+pub fn distance(a: (f32, f32), b: (f32, f32)) -> f64 {
+    EARTH_RADIUS * central_angle(a, b)
+}
+
+fn central_angle(a: (f32, f32), b: (f32, f32)) -> f64 {
+    let (lon0, lat0) = ((a.0 as f64).to_radians(), (a.1 as f64).to_radians());
+    let (lon1, lat1) = ((b.0 as f64).to_radians(), (b.1 as f64).to_radians());
+    let dlat = (lat1 - lat0) / 2.0;
+    let dlon = (lon1 - lon0) / 2.0;
+    let h = dlat.sin().powi(2) + lat0.cos() * lat1.cos() * dlon.sin().powi(2);
+    2.0 * h.sqrt().min(1.0).asin()
+}
+
+/// Length in meters of a path of flat `[lon0,lat0,lon1,lat1,...]` positions.
+pub fn length(positions: &[f32]) -> f64 {
+    positions.chunks_exact(2).zip(positions.chunks_exact(2).skip(1))
+        .map(|(a, b)| distance((a[0], a[1]), (b[0], b[1])))
+        .sum()
+}
+
+/// The point halfway along a path of flat `[lon0,lat0,lon1,lat1,...]`
+/// positions, measured in meters and interpolated linearly within a segment.
+pub fn midpoint(positions: &[f32]) -> Option<(f32, f32)> {
+    let n = positions.len() / 2;
+    if n == 0 { return None }
+    let half = length(positions) / 2.0;
+    let mut walked = 0.0;
+    for i in 1..n {
+        let a = (positions[i*2-2], positions[i*2-1]);
+        let b = (positions[i*2], positions[i*2+1]);
+        let d = distance(a, b);
+        if d > 0.0 && walked + d >= half {
+            let t = ((half - walked) / d) as f32;
+            return Some((a.0 + (b.0 - a.0) * t, a.1 + (b.1 - a.1) * t));
+        }
+        walked += d;
+    }
+    Some((positions[0], positions[1]))
+}
+
+/// Area in square meters of a spherical triangle with lon/lat corners in
+/// degrees, from its spherical excess (L'Huilier's theorem).
+pub fn triangle_area(a: (f32, f32), b: (f32, f32), c: (f32, f32)) -> f64 {
+    let (x, y, z) = (central_angle(b, c), central_angle(a, c), central_angle(a, b));
+    let s = (x + y + z) / 2.0;
+    let t = (s / 2.0).tan() * ((s - x) / 2.0).tan()
+        * ((s - y) / 2.0).tan() * ((s - z) / 2.0).tan();
+    let excess = 4.0 * t.max(0.0).sqrt().atan();
+    excess * EARTH_RADIUS * EARTH_RADIUS
+}
+
+/// Area in square meters of a triangle mesh over lon/lat `positions`, as
+/// stored in an `Area`.
+pub fn mesh_area(positions: &[f32], cells: &[usize]) -> f64 {
+    let p = |i: usize| (positions[i*2], positions[i*2+1]);
+    cells.chunks_exact(3)
+        .filter(|c| c.iter().all(|i| i*2+1 < positions.len()))
+        .map(|c| triangle_area(p(c[0]), p(c[1]), p(c[2])))
+        .sum()
+}
+
+/// Area-weighted centroid of a triangle mesh in lon/lat degrees. Falls back
+/// to the mean of the positions when the mesh has no area.
+pub fn centroid(positions: &[f32], cells: &[usize]) -> Option<(f32, f32)> {
+    let (mut sx, mut sy, mut sa) = (0.0, 0.0, 0.0);
+    for c in cells.chunks_exact(3) {
+        if c.iter().any(|i| i*2+1 >= positions.len()) { continue }
+        let v = c.iter().map(|i| (positions[i*2] as f64, positions[i*2+1] as f64))
+            .collect::<Vec<_>>();
+        let a = ((v[1].0 - v[0].0) * (v[2].1 - v[0].1)
+            - (v[2].0 - v[0].0) * (v[1].1 - v[0].1)).abs();
+        sx += a * (v[0].0 + v[1].0 + v[2].0) / 3.0;
+        sy += a * (v[0].1 + v[1].1 + v[2].1) / 3.0;
+        sa += a;
+    }
+    if sa > 0.0 {
+        return Some(((sx / sa) as f32, (sy / sa) as f32));
+    }
+    let n = positions.len() / 2;
+    if n == 0 { return None }
+    let (x, y) = positions.chunks_exact(2)
+        .fold((0.0, 0.0), |(x, y), p| (x + p[0] as f64, y + p[1] as f64));
+    Some(((x / n as f64) as f32, (y / n as f64) as f32))
+}
+
+const SEED_CELLS: f64 = 64.0;
+
+/// The point inside a polygon farthest from its edges, found to within
+/// `precision` degrees with the polylabel algorithm. `rings` are lists of
+/// position indexes, such as those from `Area::rings()`. Distances are planar
+/// in lon/lat, which is fine for picking where to place a label. A
+/// `precision` of 0 uses a hundredth of the shorter side of the bounding box.
+pub fn pole_of_inaccessibility(
+    positions: &[f32], rings: &[Vec<usize>], precision: f64
+) -> Option<(f32, f32)> {
+    let rings = rings.iter()
+        .filter(|r| r.len() >= 3 && r.iter().all(|i| i*2+1 < positions.len()))
+        .map(|r| r.iter().map(|i| (positions[i*2] as f64, positions[i*2+1] as f64))
+            .collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+    let (mut min_x, mut min_y) = (f64::INFINITY, f64::INFINITY);
+    let (mut max_x, mut max_y) = (f64::NEG_INFINITY, f64::NEG_INFINITY);
+    for (x, y) in rings.iter().flatten() {
+        min_x = min_x.min(*x);
+        min_y = min_y.min(*y);
+        max_x = max_x.max(*x);
+        max_y = max_y.max(*y);
+    }
+    if rings.is_empty() { return None }
+    let size = (max_x - min_x).min(max_y - min_y);
+    if size <= 0.0 {
+        return Some((((min_x + max_x) / 2.0) as f32, ((min_y + max_y) / 2.0) as f32));
+    }
+    let precision = if precision > 0.0 { precision } else { size / 100.0 };
+
+    // thin areas are seeded with cells longer than their width, so the first
+    // grid has at most SEED_CELLS cells along the longer side
+    let cell = size.max((max_x - min_x).max(max_y - min_y) / SEED_CELLS);
+    let mut queue = BinaryHeap::new();
+    let h = cell / 2.0;
+    let mut x = min_x;
+    while x < max_x {
+        let mut y = min_y;
+        while y < max_y {
+            queue.push(Cell::new(x + h, y + h, h, &rings));
+            y += cell;
+        }
+        x += cell;
+    }
+    // seed with the center of the bounding box
+    let mut best = Cell::new((min_x + max_x) / 2.0, (min_y + max_y) / 2.0, 0.0, &rings);
+    while let Some(cell) = queue.pop() {
+        if cell.d > best.d { best = cell.clone() }
+        if cell.max - best.d <= precision { continue }
+        let h = cell.h / 2.0;
+        for (dx, dy) in [(-h, -h), (h, -h), (-h, h), (h, h)].iter() {
+            queue.push(Cell::new(cell.x + dx, cell.y + dy, h, &rings));
+        }
+    }
+    Some((best.x as f32, best.y as f32))
+}
+
+#[derive(Clone)]
+struct Cell {
+    x: f64,
+    y: f64,
+    h: f64,
+    // signed distance from the center to the polygon, negative outside
+    d: f64,
+    // the most any point in the cell could reach
+    max: f64,
+}
+
+impl Cell {
+    fn new(x: f64, y: f64, h: f64, rings: &[Vec<(f64, f64)>]) -> Self {
+        let d = signed_distance(x, y, rings);
+        Self { x, y, h, d, max: d + h * std::f64::consts::SQRT_2 }
+    }
+}
+
+impl PartialEq for Cell {
+    fn eq(&self, other: &Self) -> bool { self.max == other.max }
+}
+impl Eq for Cell {}
+impl PartialOrd for Cell {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> { Some(self.cmp(other)) }
+}
+impl Ord for Cell {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.max.partial_cmp(&other.max).unwrap_or(Ordering::Equal)
+    }
+}
+
+fn signed_distance(x: f64, y: f64, rings: &[Vec<(f64, f64)>]) -> f64 {
+    let mut inside = false;
+    let mut min = f64::INFINITY;
+    for ring in rings {
+        let n = ring.len();
+        for i in 0..n {
+            let a = ring[i];
+            let b = ring[(i + 1) % n];
+            if (a.1 > y) != (b.1 > y) && x < (b.0 - a.0) * (y - a.1) / (b.1 - a.1) + a.0 {
+                inside = !inside;
+            }
+            min = min.min(segment_distance(x, y, a, b));
+        }
+    }
+    if inside { min } else { -min }
+}
+
+fn segment_distance(x: f64, y: f64, a: (f64, f64), b: (f64, f64)) -> f64 {
+    let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+    let len = dx * dx + dy * dy;
+    let t = if len > 0.0 {
+        (((x - a.0) * dx + (y - a.1) * dy) / len).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    let (px, py) = (a.0 + t * dx - x, a.1 + t * dy - y);
+    (px * px + py * py).sqrt()
+}
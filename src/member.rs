@@ -26,14 +26,14 @@ impl Member {
     pub fn new(id: u64, role: MemberRole, member_type: MemberType) -> Self {
         Member { id, role, member_type, reverse: false }
     }
-    pub fn drain(members: &mut Vec<Member>, ways: &HashMap<u64, Vec<u64>>) -> () {
+    pub fn drain(members: &mut Vec<Member>, ways: &HashMap<u64, Vec<u64>>) {
         // the only members that matter for rendering purposes are inner and outer ways
-        members.drain_filter(|m| {
-            let ref_len = ways.get(&m.id).and_then(|refs| Some(refs.len()));
+        members.retain(|m| {
+            let ref_len = ways.get(&m.id).map(|refs| refs.len());
             match (&m.role,&m.member_type,ref_len) {
-                (MemberRole::Inner(),MemberType::Way(),Some(len)) => len == 0,
-                (MemberRole::Outer(),MemberType::Way(),Some(len)) => len == 0,
-                _ => true,
+                (MemberRole::Inner(),MemberType::Way(),Some(len)) => len != 0,
+                (MemberRole::Outer(),MemberType::Way(),Some(len)) => len != 0,
+                _ => false,
             }
         });
     }
@@ -122,10 +122,10 @@ impl Member {
             let elifs = vec![];
             let efils = vec![];
             let elils = vec![];
-            let fifs = first_ids.get(first).or(Some(&efifs)).unwrap();
-            let lifs = last_ids.get(first).or(Some(&elifs)).unwrap();
-            let fils = first_ids.get(last).or(Some(&efils)).unwrap();
-            let lils = last_ids.get(last).or(Some(&elils)).unwrap();
+            let fifs = first_ids.get(first).unwrap_or(&efifs);
+            let lifs = last_ids.get(first).unwrap_or(&elifs);
+            let fils = first_ids.get(last).unwrap_or(&efils);
+            let lils = last_ids.get(last).unwrap_or(&elils);
 
             let max_k = fifs.len().max(lifs.len()).max(fils.len()).max(lils.len());
             let mut found = false;
@@ -134,26 +134,26 @@ impl Member {
                 let lif = lifs.get(k);
                 let fil = fils.get(k);
                 let lil = lils.get(k);
-                if fil.is_some() && !visited.contains(&fil.unwrap()) {
-                    i = *fil.unwrap();
+                if let Some(x) = fil.filter(|x| !visited.contains(*x)) {
+                    i = *x;
                     sorted.last_mut().unwrap().reverse = false;
                     reverse = false;
                     found = true;
                     break;
-                } else if lif.is_some() && !visited.contains(&lif.unwrap()) {
-                    i = *lif.unwrap();
+                } else if let Some(x) = lif.filter(|x| !visited.contains(*x)) {
+                    i = *x;
                     sorted.last_mut().unwrap().reverse = true;
                     reverse = true;
                     found = true;
                     break;
-                } else if lil.is_some() && !visited.contains(&lil.unwrap()) {
-                    i = *lil.unwrap();
+                } else if let Some(x) = lil.filter(|x| !visited.contains(*x)) {
+                    i = *x;
                     sorted.last_mut().unwrap().reverse = false;
                     reverse = true;
                     found = true;
                     break;
-                } else if fif.is_some() && !visited.contains(&fif.unwrap()) {
-                    i = *fif.unwrap();
+                } else if let Some(x) = fif.filter(|x| !visited.contains(*x)) {
+                    i = *x;
                     //sorted.last_mut().unwrap().reverse = false;
                     reverse = false;
                     found = true;
@@ -1,13 +1,13 @@
 use crate::varint;
-use crate::{label, tags};
+use crate::{label, projection, tags, Encode, Attributes, Projection, attributes::EXTENDED};
 use desert::{ToBytesLE,FromBytesLE};
 use failure::Error;
 
 #[test]
 fn peer_node() -> Result<(), Error> {
     let id = 1831881213;
-    let lon = 12.253938100000001;
-    let lat = 54.09006660000001;
+    let lon = 12.253938;
+    let lat = 54.090065;
     let tags = vec![("name", "Neu Broderstorf"), ("aerialway", "cable_car")];
     let node = Point::from_tags(id, (lon, lat), &tags)?;
 
@@ -23,12 +23,28 @@ fn peer_node() -> Result<(), Error> {
     Ok(())
 }
 
+#[test]
+fn mercator_node() -> Result<(), Error> {
+    let tags = vec![("amenity", "cafe")];
+    let options = tags::Options { projection: Projection::Mercator, ..Default::default() };
+    let mut node = Point::from_tags_with(1, (0.0, 0.0), &tags, &options)?;
+    assert_eq!(node.point, (0.5, 0.5));
+    let bytes = node.to_bytes_le()?;
+    assert_eq!(bytes[0], 0x11);
+    assert_eq!(Point::from_bytes_le(&bytes)?, (bytes.len(),node.clone()));
+    node.project(Projection::LonLat);
+    assert_eq!(node.point, (0.0, 0.0));
+    assert_eq!(node.to_bytes_le()?[0], 0x01);
+    Ok(())
+}
+
 #[derive(Debug,Clone,PartialEq)]
 pub struct Point {
     pub id: u64,
     pub point: (f32, f32),
     pub feature_type: u64,
     pub labels: Vec<u8>,
+    pub attributes: Attributes,
 }
 
 impl Point {
@@ -39,6 +55,22 @@ impl Point {
             point,
             feature_type,
             labels,
+            attributes: Attributes::default(),
+        })
+    }
+    pub fn from_tags_with(
+        id: u64,
+        point: (f32, f32),
+        tags: &[(&str, &str)],
+        options: &tags::Options,
+    ) -> Result<Point, Error> {
+        let (feature_type, labels) = tags::parse_with(tags, options)?;
+        Ok(Point {
+            id,
+            point: projection::convert(point, Projection::LonLat, options.projection),
+            feature_type,
+            labels,
+            attributes: options.attributes(tags),
         })
     }
     pub fn new(id: u64, point: (f32, f32), feature_type: u64, labels: &[u8]) -> Point {
@@ -47,16 +79,27 @@ impl Point {
             point,
             feature_type,
             labels: labels.to_vec(),
+            attributes: Attributes::default(),
         }
     }
+    /// Convert the point to `to` and record it in the attribute section.
+    pub fn project(&mut self, to: Projection) {
+        self.point = projection::convert(self.point, self.attributes.projection, to);
+        self.attributes.projection = to;
+    }
 }
 
-impl ToBytesLE for Point {
-    fn to_bytes_le(&self) -> Result<Vec<u8>, Error> {
-        let ft_length = varint::length(self.feature_type);
-        let id_length = varint::length(self.id);
-        let mut buf = vec![0u8; 1 + ft_length + id_length + 2 * 4 + self.labels.len()];
-        buf[0] = 0x01;
+impl Encode for Point {
+    fn encoded_len(&self) -> usize {
+        1 + varint::length(self.feature_type) + varint::length(self.id)
+            + 2 * 4 + self.labels.len()
+            + if self.attributes.is_empty() { 0 } else { self.attributes.encoded_len() }
+    }
+    fn write_to(&self, buf: &mut [u8]) -> Result<usize, Error> {
+        if buf.len() < self.encoded_len() {
+            failure::bail!["buffer is too small to write node"];
+        }
+        buf[0] = if self.attributes.is_empty() { 0x01 } else { 0x01 | EXTENDED };
 
         let mut offset = 1;
         offset += varint::encode(self.feature_type, &mut buf[offset..])?;
@@ -64,14 +107,29 @@ impl ToBytesLE for Point {
 
         offset += self.point.0.write_bytes_le(&mut buf[offset..])?;
         offset += self.point.1.write_bytes_le(&mut buf[offset..])?;
-        buf[offset..].copy_from_slice(&self.labels);
+        buf[offset..offset+self.labels.len()].copy_from_slice(&self.labels);
+        offset += self.labels.len();
+        if !self.attributes.is_empty() {
+            offset += self.attributes.write_to(&mut buf[offset..])?;
+        }
+        Ok(offset)
+    }
+}
+
+impl ToBytesLE for Point {
+    fn to_bytes_le(&self) -> Result<Vec<u8>, Error> {
+        let mut buf = vec![0u8; self.encoded_len()];
+        self.write_to(&mut buf)?;
         Ok(buf)
     }
+    fn write_bytes_le(&self, dst: &mut [u8]) -> Result<usize, Error> {
+        self.write_to(dst)
+    }
 }
 
 impl FromBytesLE for Point {
     fn from_bytes_le(buf: &[u8]) -> Result<(usize,Self), Error> {
-        if buf[0] != 0x01 {
+        if buf[0] & !EXTENDED != 0x01 {
             failure::bail!["parsing node failed. expected 0x01, received 0x{:02x}", buf[0]];
         }
         let mut offset = 1;
@@ -86,6 +144,12 @@ impl FromBytesLE for Point {
         let s = label::scan(&buf[offset..])?;
         let labels = buf[offset..offset+s].to_vec();
         offset += s;
-        Ok((offset, Self { id, point: (lon,lat), feature_type, labels }))
+        let mut attributes = Attributes::default();
+        if buf[0] & EXTENDED != 0 {
+            let (s,attrs) = Attributes::from_bytes_le(&buf[offset..])?;
+            offset += s;
+            attributes = attrs;
+        }
+        Ok((offset, Self { id, point: (lon,lat), feature_type, labels, attributes }))
     }
 }
@@ -0,0 +1,379 @@
+use crate::batch::{self, Encoded, Fallback, Skipped};
+use crate::triangulate::Triangulation;
+use crate::{encode, tags, Member, MemberRole, MemberType};
+use failure::Error;
+use quick_xml::events::{BytesStart, Event};
+use std::collections::HashMap;
+use std::io::BufRead;
+use std::path::Path;
+
+#[test]
+fn read_osm_xml() -> Result<(),Error> {
+    let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+        <osm version="0.6" generator="JOSM">
+          <node id="1" lat="0.0" lon="0.0"/>
+          <node id="2" lat="0.0" lon="1.0">
+            <tag k="amenity" v="cafe"/>
+            <tag k="name" v="Caf&#233; &amp; Bar"/>
+          </node>
+          <node id="-3" action="modify" lat="1.0" lon="1.0"/>
+          <node id="4" action="delete" lat="1.0" lon="0.0"/>
+          <way id="10">
+            <nd ref="1"/><nd ref="2"/><nd ref="-3"/><nd ref="1"/>
+            <tag k="building" v="yes"/>
+          </way>
+          <relation id="20">
+            <member type="way" ref="10" role="outer"/>
+            <member type="node" ref="2" role=""/>
+            <tag k="type" v="multipolygon"/>
+          </relation>
+        </osm>"#;
+    let elements = Reader::from_xml(xml).collect::<Result<Vec<OsmElement>,Error>>()?;
+    assert_eq!(elements.len(), 5);
+    assert_eq!(elements[1], OsmElement::Node(Node {
+        id: 2, point: (1.0, 0.0),
+        tags: vec![("amenity".into(), "cafe".into()), ("name".into(), "Café & Bar".into())],
+    }));
+    assert_eq!(elements[2].id(), -3i64 as u64);
+    assert_eq!(elements[3], OsmElement::Way(Way {
+        id: 10, refs: vec![1, 2, -3i64 as u64, 1], tags: vec![("building".into(), "yes".into())],
+    }));
+    match &elements[4] {
+        OsmElement::Relation(r) => assert_eq!(r.members, vec![
+            Member::new(10, MemberRole::Outer(), MemberType::Way()),
+            Member::new(2, MemberRole::Unused(), MemberType::Node()),
+        ]),
+        x => panic!["expected relation, got {:?}", x],
+    }
+
+    let (records, skipped, fallbacks) = encode_str(xml)?;
+    assert_eq!(records.len(), 5);
+    assert!(skipped.is_empty() && fallbacks.is_empty());
+    assert!(matches![crate::decode(&records[3])?, crate::Feature::Area(_)]);
+    assert!(matches![crate::decode(&records[4])?, crate::Feature::Area(_)]);
+    let options = tags::Options { projection: crate::Projection::Mercator, ..Default::default() };
+    let (projected, _, _) = encode_str_with(xml, &options)?;
+    assert_eq!(projected[1], encode::node_with(2, (1.0, 0.0), &[("amenity", "cafe"), ("name", "Café & Bar")], &options)?);
+    assert!(Reader::from_xml("<osm><node id=\"x\" lat=\"0\" lon=\"0\"/></osm>").next().unwrap().is_err());
+    assert_eq!(elements[3].to_batch(), crate::batch::Element::Way {
+        id: 10, tags: vec![("building", "yes")], refs: vec![1, 2, -3i64 as u64, 1],
+    });
+
+    // hidden elements are left out and a way missing a node is reported
+    let xml = r#"<osm>
+          <node id="1" lat="0.0" lon="0.0"/>
+          <node id="2" lat="0.0" lon="1.0" visible="false"/>
+          <node id="3" lat="1.0" lon="1.0" visible="true"/>
+          <way id="10"><nd ref="1"/><nd ref="3"/><tag k="highway" v="path"/></way>
+          <way id="11"><nd ref="1"/><nd ref="2"/><tag k="highway" v="path"/></way>
+        </osm>"#;
+    assert_eq!(Reader::from_xml(xml).count(), 4);
+    let (records, skipped, _) = encode_str(xml)?;
+    assert_eq!(records.len(), 3);
+    assert_eq!(skipped.len(), 1);
+    assert_eq!((skipped[0].index, skipped[0].id), (3, 11));
+    assert!(skipped[0].error.to_string().starts_with("way 11: "));
+
+    // a figure-eight building falls back to the scanline triangulation
+    let xml = r#"<osm>
+          <node id="1" lat="0.0" lon="0.0"/>
+          <node id="2" lat="2.0" lon="2.0"/>
+          <node id="3" lat="0.0" lon="2.0"/>
+          <node id="4" lat="2.0" lon="0.0"/>
+          <way id="10"><nd ref="1"/><nd ref="2"/><nd ref="3"/><nd ref="4"/><nd ref="1"/><tag k="building" v="yes"/></way>
+        </osm>"#;
+    let (records, _, fallbacks) = encode_str(xml)?;
+    assert_eq!(records.len(), 5);
+    assert_eq!(fallbacks.len(), 1);
+    assert_eq!((fallbacks[0].index, fallbacks[0].id), (4, 10));
+    Ok(())
+}
+
+#[derive(Debug,Clone,PartialEq)]
+pub struct Node {
+    pub id: u64,
+    pub point: (f32, f32),
+    pub tags: Vec<(String,String)>,
+}
+
+#[derive(Debug,Clone,PartialEq)]
+pub struct Way {
+    pub id: u64,
+    pub refs: Vec<u64>,
+    pub tags: Vec<(String,String)>,
+}
+
+#[derive(Debug,Clone,PartialEq)]
+pub struct Relation {
+    pub id: u64,
+    pub members: Vec<Member>,
+    pub tags: Vec<(String,String)>,
+}
+
+/// A node, way or relation from an `.osm` file. Negative ids, as used by
+/// editors for new objects, wrap around as they do with `as u64`.
+#[derive(Debug,Clone,PartialEq)]
+pub enum OsmElement {
+    Node(Node),
+    Way(Way),
+    Relation(Relation),
+}
+
+impl OsmElement {
+    pub fn id(&self) -> u64 {
+        match self {
+            Self::Node(node) => node.id,
+            Self::Way(way) => way.id,
+            Self::Relation(relation) => relation.id,
+        }
+    }
+    pub fn tags(&self) -> Vec<(&str,&str)> {
+        let tags = match self {
+            Self::Node(node) => &node.tags,
+            Self::Way(way) => &way.tags,
+            Self::Relation(relation) => &relation.tags,
+        };
+        tags.iter().map(|(k,v)| (k.as_str(), v.as_str())).collect()
+    }
+    /// Borrow the element as a `batch::Element`, to encode many at once with
+    /// a `Batch`.
+    pub fn to_batch(&self) -> batch::Element<'_> {
+        let tags = self.tags();
+        match self {
+            Self::Node(node) => batch::Element::Node { id: node.id, point: node.point, tags },
+            Self::Way(way) => batch::Element::Way { id: way.id, tags, refs: way.refs.clone() },
+            Self::Relation(relation) => batch::Element::Relation {
+                id: relation.id, tags, members: relation.members.clone(),
+            },
+        }
+    }
+    fn kind(&self) -> &'static str {
+        match self {
+            Self::Node(_) => "node",
+            Self::Way(_) => "way",
+            Self::Relation(_) => "relation",
+        }
+    }
+}
+
+pub fn member_role(role: &str) -> MemberRole {
+    match role {
+        "inner" => MemberRole::Inner(),
+        "outer" => MemberRole::Outer(),
+        _ => MemberRole::Unused(),
+    }
+}
+
+pub fn member_type(member_type: &str) -> Result<MemberType,Error> {
+    Ok(match member_type {
+        "node" => MemberType::Node(),
+        "way" => MemberType::Way(),
+        "relation" => MemberType::Relation(),
+        x => failure::bail!["unknown member type {:?}", x],
+    })
+}
+
+/// Reads elements from OSM XML in document order. Elements marked
+/// `action="delete"` by an editor or `visible="false"`, as deleted versions
+/// are in history exports, are left out.
+pub struct Reader<R: BufRead> {
+    reader: quick_xml::Reader<R>,
+    buf: Vec<u8>,
+}
+
+impl<'a> Reader<&'a [u8]> {
+    pub fn from_xml(xml: &'a str) -> Self {
+        Self::new(xml.as_bytes())
+    }
+}
+
+impl Reader<std::io::BufReader<std::fs::File>> {
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self,Error> {
+        Ok(Self::new(std::io::BufReader::new(std::fs::File::open(path)?)))
+    }
+}
+
+impl<R: BufRead> Reader<R> {
+    pub fn new(reader: R) -> Self {
+        Self { reader: quick_xml::Reader::from_reader(reader), buf: vec![] }
+    }
+
+    fn next_element(&mut self) -> Result<Option<OsmElement>,Error> {
+        let mut element: Option<OsmElement> = None;
+        let mut deleted = false;
+        loop {
+            self.buf.clear();
+            let (e, empty) = match self.reader.read_event_into(&mut self.buf)? {
+                Event::Start(e) => (e.into_owned(), false),
+                Event::Empty(e) => (e.into_owned(), true),
+                Event::End(e) => {
+                    match e.name().as_ref() {
+                        b"node" | b"way" | b"relation" => {
+                            if deleted { element = None; deleted = false; continue }
+                            if element.is_some() { return Ok(element) }
+                        },
+                        _ => {},
+                    }
+                    continue;
+                },
+                Event::Eof => return Ok(None),
+                _ => continue,
+            };
+            let attrs = attributes(&e)?;
+            let get = |k: &str| attrs.get(k).ok_or_else(|| failure::format_err![
+                "missing {} attribute on <{}>", k, String::from_utf8_lossy(e.name().as_ref())
+            ]);
+            let started = match e.name().as_ref() {
+                b"node" => Some(OsmElement::Node(Node {
+                    id: parse_id(get("id")?)?,
+                    point: (get("lon")?.parse()?, get("lat")?.parse()?),
+                    tags: vec![],
+                })),
+                b"way" => Some(OsmElement::Way(Way { id: parse_id(get("id")?)?, refs: vec![], tags: vec![] })),
+                b"relation" => Some(OsmElement::Relation(Relation {
+                    id: parse_id(get("id")?)?, members: vec![], tags: vec![],
+                })),
+                b"tag" => {
+                    let tag = (get("k")?.clone(), get("v")?.clone());
+                    match &mut element {
+                        Some(OsmElement::Node(Node { tags, .. })) => tags.push(tag),
+                        Some(OsmElement::Way(Way { tags, .. })) => tags.push(tag),
+                        Some(OsmElement::Relation(Relation { tags, .. })) => tags.push(tag),
+                        None => {},
+                    }
+                    None
+                },
+                b"nd" => {
+                    if let Some(OsmElement::Way(way)) = &mut element {
+                        way.refs.push(parse_id(get("ref")?)?);
+                    }
+                    None
+                },
+                b"member" => {
+                    if let Some(OsmElement::Relation(relation)) = &mut element {
+                        relation.members.push(Member::new(
+                            parse_id(get("ref")?)?,
+                            member_role(attrs.get("role").map(|r| r.as_str()).unwrap_or("")),
+                            member_type(get("type")?)?,
+                        ));
+                    }
+                    None
+                },
+                _ => None,
+            };
+            if let Some(started) = started {
+                let delete = attrs.get("action").map(|a| a == "delete").unwrap_or(false)
+                    || attrs.get("visible").map(|v| v == "false").unwrap_or(false);
+                if empty {
+                    if !delete { return Ok(Some(started)) }
+                } else {
+                    element = Some(started);
+                    deleted = delete;
+                }
+            }
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for Reader<R> {
+    type Item = Result<OsmElement,Error>;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_element().transpose()
+    }
+}
+
+fn attributes(e: &BytesStart) -> Result<HashMap<String,String>,Error> {
+    let mut attrs = HashMap::new();
+    for attr in e.attributes() {
+        let attr = attr?;
+        let key = String::from_utf8(attr.key.as_ref().to_vec())?;
+        attrs.insert(key, attr.unescape_value()?.into_owned());
+    }
+    Ok(attrs)
+}
+
+fn parse_id(id: &str) -> Result<u64,Error> {
+    Ok(id.parse::<i64>()? as u64)
+}
+
+/// Encodes elements in document order with the `encode` functions, keeping
+/// node positions and way refs for the ways and relations that follow.
+#[derive(Default)]
+pub struct Encoder {
+    pub nodes: HashMap<u64,(f32,f32)>,
+    pub ways: HashMap<u64,Vec<u64>>,
+    pub options: tags::Options,
+}
+
+impl Encoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Parse tags with `options`, as the `encode::*_with` functions do.
+    pub fn with_options(options: tags::Options) -> Self {
+        Self { options, ..Self::default() }
+    }
+    /// Encode an element, returning an empty record for elements that are
+    /// skipped, as `encode` does.
+    pub fn encode(&mut self, element: &OsmElement) -> Result<Vec<u8>,Error> {
+        let mut buf = vec![];
+        self.encode_into(element, &mut buf)?;
+        Ok(buf)
+    }
+    /// Append an element to `buf`, returning the number of bytes written and,
+    /// for areas, how their rings were triangulated, as
+    /// `encode::way_with_into` does.
+    pub fn encode_into(
+        &mut self, element: &OsmElement, buf: &mut Vec<u8>
+    ) -> Result<(usize, Option<Triangulation>),Error> {
+        let tags = element.tags();
+        match element {
+            OsmElement::Node(node) => {
+                self.nodes.insert(node.id, node.point);
+                Ok((encode::node_with_into(node.id, node.point, &tags, &self.options, buf)?, None))
+            },
+            OsmElement::Way(way) => {
+                self.ways.insert(way.id, way.refs.clone());
+                encode::way_with_into(way.id, &tags, &way.refs, &self.nodes, &self.options, buf)
+            },
+            OsmElement::Relation(relation) => {
+                let (nodes, ways, members) = (&self.nodes, &self.ways, &relation.members);
+                encode::relation_with_into(relation.id, &tags, members, nodes, ways, &self.options, buf)
+            },
+        }
+    }
+}
+
+/// Encode every element of an OSM XML document, leaving out skipped ones.
+/// Elements that fail to encode, such as a way with a node missing from a
+/// clipped extract, are left out too and returned as `Skipped`, and areas
+/// triangulated with the fallback are returned as `Fallback`, as a `Batch`
+/// does. Malformed XML still fails the whole document.
+pub fn encode_str(xml: &str) -> Result<Encoded,Error> {
+    encode_str_with(xml, &tags::DEFAULT_OPTIONS)
+}
+
+/// Like `encode_str`, parsing tags with `options`.
+pub fn encode_str_with(xml: &str, options: &tags::Options) -> Result<Encoded,Error> {
+    let mut encoder = Encoder::with_options(options.clone());
+    let mut records = vec![];
+    let (mut skipped, mut fallbacks) = (vec![], vec![]);
+    for (index, element) in Reader::from_xml(xml).enumerate() {
+        let element = element?;
+        let mut record = vec![];
+        match encoder.encode_into(&element, &mut record) {
+            Ok((_, method)) => {
+                if let Some(Triangulation::Fallback { coverage }) = method {
+                    fallbacks.push(Fallback { index, id: element.id(), coverage });
+                }
+                if !record.is_empty() { records.push(record) }
+            },
+            Err(error) => skipped.push(Skipped {
+                index,
+                id: element.id(),
+                error: format_err!["{} {}: {}", element.kind(), element.id(), error],
+            }),
+        }
+    }
+    Ok((records, skipped, fallbacks))
+}
@@ -0,0 +1,94 @@
+use failure::Error;
+use std::borrow::Cow;
+use std::f64::consts::PI;
+
+#[test]
+fn mercator_round_trip() {
+    assert_eq!(to_mercator((0.0, 0.0)), (0.5, 0.5));
+    assert_eq!(to_mercator((-180.0, MAX_LATITUDE as f32)), (0.0, 0.0));
+    assert_eq!(to_mercator((180.0, -MAX_LATITUDE as f32)), (1.0, 1.0));
+    // the poles are clamped to the edge of the square instead of infinity
+    assert_eq!(to_mercator((0.0, 90.0)), (0.5, 0.0));
+    assert_eq!(to_mercator((0.0, -90.0)), (0.5, 1.0));
+    for (lon,lat) in [(12.253938, 54.090065), (-122.4, 37.8), (179.9, -84.0)].iter() {
+        let (x,y) = from_mercator(to_mercator((*lon, *lat)));
+        assert!((x - lon).abs() < 1e-4 && (y - lat).abs() < 1e-4, "{} {}", x, y);
+    }
+    let mut positions = vec![0.0, 0.0, 90.0, 45.0];
+    project(&mut positions, Projection::LonLat, Projection::Mercator);
+    assert_eq!(&positions[0..3], &[0.5, 0.5, 0.75]);
+    project(&mut positions, Projection::Mercator, Projection::LonLat);
+    assert!((positions[3] - 45.0).abs() < 1e-4);
+}
+
+/// Latitude in degrees at which Web Mercator turns into a square.
+pub const MAX_LATITUDE: f64 = 85.0511287798066;
+
+/// The coordinate system a record's positions are stored in.
+///
+/// `Mercator` positions are normalized Web Mercator: `x` runs from 0 at
+/// -180° to 1 at 180° and `y` from 0 at the north edge to 1 at the south edge,
+/// as with tile coordinates. Latitudes beyond `MAX_LATITUDE` are clamped.
+#[derive(Debug,Clone,Copy,PartialEq,Default)]
+pub enum Projection {
+    #[default]
+    LonLat,
+    Mercator,
+}
+
+impl Projection {
+    pub fn id(&self) -> u64 {
+        match self {
+            Projection::LonLat => 0,
+            Projection::Mercator => 1,
+        }
+    }
+    pub fn from_id(id: u64) -> Result<Self,Error> {
+        match id {
+            0 => Ok(Projection::LonLat),
+            1 => Ok(Projection::Mercator),
+            x => failure::bail!["unknown projection {}", x],
+        }
+    }
+}
+
+pub fn to_mercator(p: (f32, f32)) -> (f32, f32) {
+    let lon = p.0 as f64;
+    let lat = (p.1 as f64).clamp(-MAX_LATITUDE, MAX_LATITUDE).to_radians();
+    let x = (lon + 180.0) / 360.0;
+    let y = 0.5 - (PI / 4.0 + lat / 2.0).tan().ln() / (2.0 * PI);
+    (x as f32, y.clamp(0.0, 1.0) as f32)
+}
+
+pub fn from_mercator(p: (f32, f32)) -> (f32, f32) {
+    let lon = (p.0 as f64) * 360.0 - 180.0;
+    let lat = (PI * (1.0 - 2.0 * p.1 as f64)).sinh().atan().to_degrees();
+    (lon as f32, lat as f32)
+}
+
+/// Convert a point between projections.
+pub fn convert(p: (f32, f32), from: Projection, to: Projection) -> (f32, f32) {
+    match (from, to) {
+        (Projection::LonLat, Projection::Mercator) => to_mercator(p),
+        (Projection::Mercator, Projection::LonLat) => from_mercator(p),
+        _ => p,
+    }
+}
+
+/// Convert flat `[x0,y0,x1,y1,...]` positions between projections in place.
+pub fn project(positions: &mut [f32], from: Projection, to: Projection) {
+    if from == to { return }
+    for p in positions.chunks_exact_mut(2) {
+        let (x,y) = convert((p[0], p[1]), from, to);
+        p[0] = x;
+        p[1] = y;
+    }
+}
+
+/// `positions` in lon/lat, copied only when they need converting.
+pub fn lonlat(positions: &[f32], from: Projection) -> Cow<'_, [f32]> {
+    if from == Projection::LonLat { return Cow::Borrowed(positions) }
+    let mut positions = positions.to_vec();
+    project(&mut positions, from, Projection::LonLat);
+    Cow::Owned(positions)
+}
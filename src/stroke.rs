@@ -0,0 +1,216 @@
+use std::f32::consts::PI;
+
+#[test]
+fn stroke_segment() {
+    let stroke = tessellate(&[0.0, 0.0, 2.0, 0.0], &StrokeOptions::default());
+    assert_eq!(stroke.len(), 4);
+    assert_eq!(stroke.cells.len(), 6);
+    assert_eq!(stroke.offsets, vec![0.0, 1.0, 0.0, -1.0, 0.0, -1.0, 0.0, 1.0]);
+    let square = StrokeOptions { cap: Cap::Square, ..Default::default() };
+    let stroke = tessellate(&[0.0, 0.0, 2.0, 0.0], &square);
+    assert_eq!(stroke.len(), 4 + 2*4);
+    assert!(stroke.offsets.chunks(2).any(|o| o == [-1.0, 1.0]));
+    let round = StrokeOptions { cap: Cap::Round, round_segments: 4, ..Default::default() };
+    let stroke = tessellate(&[0.0, 0.0, 2.0, 0.0], &round);
+    // a center and 5 rim vertices for each half circle
+    assert_eq!(stroke.len(), 4 + 2*6);
+    assert_eq!(stroke.cells.len(), 6 + 2*4*3);
+    assert!(tessellate(&[1.0, 1.0], &StrokeOptions::default()).is_empty());
+}
+
+#[test]
+fn stroke_joins() {
+    let corner = [0.0, 0.0, 1.0, 0.0, 1.0, 1.0];
+    let options = |join| StrokeOptions { join, round_segments: 8, ..Default::default() };
+    let miter = tessellate(&corner, &options(Join::Miter));
+    assert_eq!(miter.len(), 2*4 + 4);
+    let (x,y) = (miter.offsets[2*10], miter.offsets[2*10+1]);
+    assert!((x - 1.0).abs() < 1e-6 && (y + 1.0).abs() < 1e-6, "{} {}", x, y);
+    assert_eq!(tessellate(&corner, &options(Join::Bevel)).len(), 2*4 + 3);
+    // a quarter turn takes half of the 8 steps of a half circle
+    assert_eq!(tessellate(&corner, &options(Join::Round)).len(), 2*4 + 6);
+    // a sharp turn falls back to a bevel past the miter limit
+    let sharp = [0.0, 0.0, 1.0, 0.0, 0.0, 0.1];
+    assert_eq!(tessellate(&sharp, &options(Join::Miter)).len(), 2*4 + 3);
+    // no join is needed between segments going straight on
+    assert_eq!(tessellate(&[0.0, 0.0, 1.0, 0.0, 2.0, 0.0], &options(Join::Round)).len(), 2*4);
+}
+
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub enum Join {
+    Miter,
+    Round,
+    Bevel,
+}
+
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub enum Cap {
+    Butt,
+    Round,
+    Square,
+}
+
+#[derive(Debug,Clone,PartialEq)]
+pub struct StrokeOptions {
+    pub join: Join,
+    pub cap: Cap,
+    /// Longest miter in half line widths before a join is beveled instead.
+    pub miter_limit: f32,
+    /// Triangles in a half circle for round joins and caps.
+    pub round_segments: usize,
+}
+
+impl Default for StrokeOptions {
+    fn default() -> Self {
+        Self { join: Join::Miter, cap: Cap::Butt, miter_limit: 2.0, round_segments: 8 }
+    }
+}
+
+/// Triangles for drawing a line at any width. Each vertex is a point on the
+/// line in `positions` and an extrusion vector in `offsets`, one half line
+/// width long for the sides of segments, so a vertex shader places it at
+/// `position + offset * width / 2` in screen space. `cells` holds three
+/// vertex indexes per counter-clockwise triangle.
+#[derive(Debug,Clone,Default,PartialEq)]
+pub struct Stroke {
+    pub positions: Vec<f32>,
+    pub offsets: Vec<f32>,
+    pub cells: Vec<u32>,
+}
+
+impl Stroke {
+    /// The number of vertices.
+    pub fn len(&self) -> usize {
+        self.positions.len() / 2
+    }
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+    fn vertex(&mut self, p: (f32, f32), offset: (f32, f32)) -> u32 {
+        self.positions.extend_from_slice(&[p.0, p.1]);
+        self.offsets.extend_from_slice(&[offset.0, offset.1]);
+        (self.len() - 1) as u32
+    }
+    // a fan around `p` sweeping `angle` radians from `from`
+    fn fan(&mut self, p: (f32, f32), from: (f32, f32), angle: f32, segments: usize) {
+        let steps = ((angle.abs() / PI * segments as f32).ceil() as usize).max(1);
+        let center = self.vertex(p, (0.0, 0.0));
+        let start = from.1.atan2(from.0);
+        for k in 0..=steps {
+            let a = start + angle * (k as f32) / (steps as f32);
+            self.vertex(p, (a.cos(), a.sin()));
+        }
+        for k in 0..steps as u32 {
+            let (i, j) = (center + 1 + k, center + 2 + k);
+            if angle >= 0.0 {
+                self.cells.extend_from_slice(&[center, i, j]);
+            } else {
+                self.cells.extend_from_slice(&[center, j, i]);
+            }
+        }
+    }
+    fn triangle(&mut self, a: u32, b: u32, c: u32, ccw: bool) {
+        if ccw {
+            self.cells.extend_from_slice(&[a, b, c]);
+        } else {
+            self.cells.extend_from_slice(&[a, c, b]);
+        }
+    }
+}
+
+/// Tessellate a line of flat `[x0,y0,x1,y1,...]` positions. Directions are
+/// taken in position units, so project lon/lat to Mercator first for even
+/// widths away from the equator. Repeated points are skipped.
+pub fn tessellate(positions: &[f32], options: &StrokeOptions) -> Stroke {
+    let mut points: Vec<(f32, f32)> = Vec::with_capacity(positions.len() / 2);
+    for p in positions.chunks_exact(2) {
+        if points.last() != Some(&(p[0], p[1])) { points.push((p[0], p[1])) }
+    }
+    let mut stroke = Stroke::default();
+    if points.len() < 2 { return stroke }
+    let dirs = points.windows(2).map(|w| {
+        let (dx, dy) = (w[1].0 - w[0].0, w[1].1 - w[0].1);
+        let len = (dx * dx + dy * dy).sqrt();
+        (dx / len, dy / len)
+    }).collect::<Vec<_>>();
+    let left = |d: (f32, f32)| (-d.1, d.0);
+
+    for (w, d) in points.windows(2).zip(dirs.iter()) {
+        let n = left(*d);
+        let a0 = stroke.vertex(w[0], n);
+        let a1 = stroke.vertex(w[0], (-n.0, -n.1));
+        let b1 = stroke.vertex(w[1], (-n.0, -n.1));
+        let b0 = stroke.vertex(w[1], n);
+        stroke.cells.extend_from_slice(&[a1, b1, b0, a1, b0, a0]);
+    }
+
+    for (i, p) in points.iter().enumerate().skip(1).take(points.len() - 2) {
+        let (d0, d1) = (dirs[i-1], dirs[i]);
+        let cross = d0.0 * d1.1 - d0.1 * d1.0;
+        let dot = d0.0 * d1.0 + d0.1 * d1.1;
+        if cross.abs() < 1e-6 && dot > 0.0 { continue }
+        let turn = cross.atan2(dot);
+        // the outside of the turn is on the right of a left turn
+        let side = if turn > 0.0 { -1.0 } else { 1.0 };
+        let (n0, n1) = (left(d0), left(d1));
+        let o0 = (n0.0 * side, n0.1 * side);
+        let o1 = (n1.0 * side, n1.1 * side);
+        let mut join = options.join;
+        let m = (n0.0 + n1.0, n0.1 + n1.1);
+        let m_len = (m.0 * m.0 + m.1 * m.1).sqrt();
+        let miter = if m_len > 0.0 {
+            let m = (m.0 / m_len, m.1 / m_len);
+            let scale = 1.0 / (m.0 * n0.0 + m.1 * n0.1);
+            Some((m.0 * scale * side, m.1 * scale * side, scale))
+        } else {
+            None
+        };
+        if join == Join::Miter {
+            match miter {
+                Some((_, _, scale)) if scale <= options.miter_limit => {},
+                _ => join = Join::Bevel,
+            }
+        }
+        match join {
+            Join::Round => stroke.fan(*p, o0, turn, options.round_segments),
+            Join::Bevel => {
+                let c = stroke.vertex(*p, (0.0, 0.0));
+                let a = stroke.vertex(*p, o0);
+                let b = stroke.vertex(*p, o1);
+                stroke.triangle(c, a, b, turn > 0.0);
+            },
+            Join::Miter => {
+                let (mx, my, _) = miter.unwrap();
+                let c = stroke.vertex(*p, (0.0, 0.0));
+                let a = stroke.vertex(*p, o0);
+                let m = stroke.vertex(*p, (mx, my));
+                let b = stroke.vertex(*p, o1);
+                stroke.triangle(c, a, m, turn > 0.0);
+                stroke.triangle(c, m, b, turn > 0.0);
+            },
+        }
+    }
+
+    let first = dirs[0];
+    let last = dirs[dirs.len() - 1];
+    let ends = [
+        (points[0], (-first.0, -first.1)),
+        (points[points.len() - 1], last),
+    ];
+    for (p, out) in ends.iter() {
+        // `out` points away from the line, `n` is on its left looking out
+        let n = (-out.1, out.0);
+        match options.cap {
+            Cap::Butt => {},
+            Cap::Round => stroke.fan(*p, (-n.0, -n.1), PI, options.round_segments),
+            Cap::Square => {
+                let a = stroke.vertex(*p, (-n.0, -n.1));
+                let b = stroke.vertex(*p, (out.0 - n.0, out.1 - n.1));
+                let c = stroke.vertex(*p, (out.0 + n.0, out.1 + n.1));
+                let d = stroke.vertex(*p, n);
+                stroke.cells.extend_from_slice(&[a, b, c, a, c, d]);
+            },
+        }
+    }
+    stroke
+}
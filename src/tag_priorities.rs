@@ -1,5 +1,9 @@
+use std::collections::HashMap;
+
+pub const DEFAULT_PRIORITY: u64 = 50;
+
 pub fn get_priorities<'a>() -> Vec<(&'a str, u64)> {
-    return vec![
+    vec![
         ("aerialway.cable_car", 97),
         ("aerialway.chair_lift", 96),
         ("aeroway.aerodrome", 98),
@@ -17,5 +21,150 @@ pub fn get_priorities<'a>() -> Vec<(&'a str, u64)> {
         ("railway.*", 95),
         ("route.*", 7),
         ("sport.*", 1),
-    ];
+    ]
+}
+
+#[test]
+fn priority_rules() {
+    let rules = Priorities::default();
+    assert_eq!(rules.get(&("amenity", "university"), &[]), Some(99));
+    assert_eq!(rules.get(&("amenity", "cafe"), &[]), Some(60));
+    assert_eq!(rules.get(&("highway", "residential"), &[]), None);
+    assert_eq!(rules.priority(&("highway", "residential"), &[]), DEFAULT_PRIORITY);
+
+    let rules = Priorities::new(vec![
+        Rule::new("building.*", 20),
+        Rule::new("building.*", 5).when("amenity"),
+        Rule::new("building.church", 30),
+        Rule::new("building.church", 2).when_tag("ruins", "yes").unless("denomination"),
+    ], 1);
+    let building = ("building", "yes");
+    let church = ("building", "church");
+    assert_eq!(rules.priority(&building, &[building]), 20);
+    assert_eq!(rules.priority(&building, &[building, ("amenity", "cafe")]), 5);
+    assert_eq!(rules.priority(&church, &[church, ("amenity", "cafe")]), 5);
+    assert_eq!(rules.priority(&church, &[church, ("ruins", "yes")]), 2);
+    assert_eq!(rules.priority(&church, &[church, ("ruins", "yes"), ("denomination", "x")]), 30);
+    assert_eq!(rules.priority(&("highway", "path"), &[]), 1);
+}
+
+/// A condition on the other tags of a feature that must hold for a `Rule` to
+/// apply.
+#[derive(Debug,Clone,PartialEq)]
+pub enum Condition {
+    /// some tag has this key
+    Has(String),
+    /// some tag has this key and value
+    HasTag(String, String),
+    /// no tag has this key
+    Lacks(String),
+}
+
+impl Condition {
+    fn check(&self, tags: &[(&str, &str)]) -> bool {
+        match self {
+            Condition::Has(k) => tags.iter().any(|t| t.0 == k),
+            Condition::HasTag(k,v) => tags.iter().any(|t| t.0 == k && t.1 == v),
+            Condition::Lacks(k) => !tags.iter().any(|t| t.0 == k),
+        }
+    }
+}
+
+/// A priority for tags matching `key.value` or `key.*`, optionally limited
+/// to features whose tags satisfy every condition.
+#[derive(Debug,Clone,PartialEq)]
+pub struct Rule {
+    pub key: String,
+    /// `None` for a `key.*` wildcard
+    pub value: Option<String>,
+    pub conditions: Vec<Condition>,
+    pub priority: u64,
+}
+
+impl Rule {
+    /// Create a rule from a `key.value` or `key.*` pattern.
+    pub fn new(pattern: &str, priority: u64) -> Self {
+        let (key, value) = match pattern.find('.') {
+            Some(i) => (&pattern[..i], &pattern[i+1..]),
+            None => (pattern, "*"),
+        };
+        Self {
+            key: key.to_string(),
+            value: if value == "*" { None } else { Some(value.to_string()) },
+            conditions: vec![],
+            priority,
+        }
+    }
+    pub fn when(mut self, key: &str) -> Self {
+        self.conditions.push(Condition::Has(key.to_string()));
+        self
+    }
+    pub fn when_tag(mut self, key: &str, value: &str) -> Self {
+        self.conditions.push(Condition::HasTag(key.to_string(), value.to_string()));
+        self
+    }
+    pub fn unless(mut self, key: &str) -> Self {
+        self.conditions.push(Condition::Lacks(key.to_string()));
+        self
+    }
+    fn specificity(&self) -> (usize, bool) {
+        (self.conditions.len(), self.value.is_some())
+    }
+}
+
+/// A compiled set of priority rules. Rules are grouped by key so that looking
+/// up a tag does not format or scan unrelated patterns.
+///
+/// When several rules match a tag, the most specific one wins: rules with more
+/// conditions beat rules with fewer, and `key.value` beats `key.*`. Equally
+/// specific rules resolve to the highest priority. Tags that match no rule get
+/// the default priority.
+#[derive(Debug,Clone,PartialEq)]
+pub struct Priorities {
+    rules: HashMap<String, Vec<Rule>>,
+    default: u64,
+}
+
+impl Default for Priorities {
+    fn default() -> Self {
+        Self::new(
+            get_priorities().iter().map(|(p,n)| Rule::new(p, *n)).collect(),
+            DEFAULT_PRIORITY
+        )
+    }
+}
+
+impl Priorities {
+    pub fn new(rules: Vec<Rule>, default: u64) -> Self {
+        let mut grouped: HashMap<String, Vec<Rule>> = HashMap::new();
+        for rule in rules {
+            grouped.entry(rule.key.clone()).or_default().push(rule);
+        }
+        for group in grouped.values_mut() {
+            // most specific first, then highest priority
+            group.sort_by(|a,b| {
+                b.specificity().cmp(&a.specificity()).then_with(|| b.priority.cmp(&a.priority))
+            });
+        }
+        Self { rules: grouped, default }
+    }
+    pub fn default_priority(&self) -> u64 {
+        self.default
+    }
+
+    /// Priority of the best rule matching `tag`, given all of the feature's
+    /// `tags` for evaluating conditions, or `None` if no rule matches.
+    pub fn get(&self, tag: &(&str, &str), tags: &[(&str, &str)]) -> Option<u64> {
+        self.rules.get(tag.0)?.iter()
+            .find(|r| {
+                r.value.as_ref().map(|v| v == tag.1).unwrap_or(true)
+                    && r.conditions.iter().all(|c| c.check(tags))
+            })
+            .map(|r| r.priority)
+    }
+
+    /// Like `get`, falling back to the default priority.
+    pub fn priority(&self, tag: &(&str, &str), tags: &[(&str, &str)]) -> u64 {
+        self.get(tag, tags).unwrap_or(self.default)
+    }
 }
@@ -1,17 +1,17 @@
+use crate::label;
+use crate::{Attributes, Dictionary, Projection};
 use crate::osm_types;
 use crate::tag_priorities;
 use crate::varint;
 use failure::Error;
-use regex::Regex;
-use std::collections::HashMap;
 
 #[test]
 fn two_tags_one_has_no_priority() {
     use crate::node::Point;
     use desert::ToBytesLE;
     let id = 1831881213;
-    let lon = 12.253938100000001;
-    let lat = 54.09006660000001;
+    let lon = 12.253938;
+    let lat = 54.090065;
     let tags = vec![
         ("name", "I am Stoplight"),
         ("highway", "traffic_signals"),
@@ -30,8 +30,8 @@ fn two_tags_both_valid_priorities() {
     use crate::node::Point;
     use desert::ToBytesLE;
     let id = 1831881213;
-    let lon = 12.253938100000001;
-    let lat = 54.09006660000001;
+    let lon = 12.253938;
+    let lat = 54.090065;
     let tags = vec![
         ("name", "I am Stoplight"),
         ("route", "canoe"),
@@ -51,8 +51,8 @@ fn two_tags_same_priority() {
     use crate::node::Point;
     use desert::ToBytesLE;
     let id = 1831881213;
-    let lon = 12.253938100000001;
-    let lat = 54.09006660000001;
+    let lon = 12.253938;
+    let lat = 54.090065;
     let tags = vec![
         ("name", "I am Stoplight"),
         ("railway", "wash"),
@@ -80,127 +80,179 @@ fn two_tags_same_priority() {
     );
 }
 
+#[test]
+fn parse_with_options() -> Result<(), Error> {
+    use crate::label::{LabelRule, LabelRules};
+    let types = osm_types::get_types();
+    let tags = vec![("name", "Mt Foo"), ("ele", "1203"), ("natural", "peak")];
+    let options = Options {
+        labels: LabelRules::default().include(LabelRule::new("^ele$")?),
+        ..Options::default()
+    };
+    let (t, labels) = parse_with(&tags, &options)?;
+    assert_eq!(t, *types.get("natural.peak").unwrap());
+    assert_eq!(osm_types::get_type("natural", "peak"), Some(t));
+    assert_eq!(osm_types::get_type("natural", "peak.x"), None);
+    assert_eq!(labels, label::encode(&["=Mt Foo", "ele=1203"])?);
+    assert_eq!(parse(&tags)?.1, label::encode(&["=Mt Foo"])?);
+    Ok(())
+}
+
+#[test]
+fn label_length_matches_parse() -> Result<(),Error> {
+    let long = "x".repeat(2000);
+    let tags = vec![
+        ("name", "Main St"), ("highway", "primary"), ("alt_name", "High St"),
+        ("name:de", long.as_str()),
+    ];
+    let (_,labels) = parse(&tags)?;
+    assert_eq!(get_label_length(&tags), labels.len());
+    assert_eq!(get_tag(&("name:de", "Hauptstraße")), "de=Hauptstraße");
+    assert_eq!(get_tag(&("highway", "primary")), "highway=primary");
+    Ok(())
+}
+
+#[test]
+fn unprioritized_type_uses_default() {
+    let types = osm_types::get_types();
+    // highway.residential has no rule, so it ranks at the default of 50,
+    // above landuse.* at 10
+    let (t,_) = parse(&[("landuse", "grass"), ("highway", "residential")]).unwrap();
+    assert_eq!(t, *types.get("highway.residential").unwrap());
+    assert_eq!(get_tag_priority(&("highway", "residential")), None);
+    assert_eq!(get_tag_priority(&("landuse", "grass")), Some(10));
+}
+
+#[test]
+fn equal_priority_is_order_independent() {
+    let types = osm_types::get_types();
+    let tags = vec![("highway", "residential"), ("natural", "water")];
+    let (t0,_) = parse(&tags).unwrap();
+    let (t1,_) = parse(&tags.iter().rev().cloned().collect::<Vec<_>>()).unwrap();
+    assert_eq!(t0, t1);
+    assert_eq!(t0, *types.get("highway.residential").unwrap());
+    let ranked = classify(&[
+        ("natural", "water"), ("name", "x"), ("power", "cable"), ("highway", "residential"),
+    ]);
+    assert_eq!(
+        ranked.iter().map(|c| (c.key,c.value,c.priority)).collect::<Vec<_>>(),
+        vec![("power","cable",100), ("highway","residential",50), ("natural","water",50)]
+    );
+    assert_eq!(ranked[0].feature_type, *types.get("power.cable").unwrap());
+}
+
+/// The label `parse` writes for `tag`, or `key=value` for tags it doesn't
+/// keep.
 pub fn get_tag(tag: &(&str, &str)) -> String {
-    lazy_static! {
-        static ref RE: Regex = Regex::new("^(|[^:]+_)name($|:)").unwrap();
-    }
-    let pre = RE.replace(tag.0, "");
-    return (pre + "=" + tag.1).to_string();
+    DEFAULT_OPTIONS.labels.label(tag).unwrap_or_else(|| format!("{}={}", tag.0, tag.1))
 }
 
 pub fn get_tag_length(tag: &(&str, &str)) -> usize {
     get_tag(tag).len()
 }
 
+/// Length of the labels `parse` writes for `tags`, including the terminator.
 pub fn get_label_length(tags: &[(&str, &str)]) -> usize {
-    lazy_static! {
-        static ref RE: Regex = Regex::new("^(|[^:]+_)name($|:)").unwrap();
-    }
-    let mut label_len = 1;
-    for tag in tags {
-        // skip all tags that aren't the name tag
-        let is_name_tag = RE.find(tag.0);
-        match is_name_tag {
-            Some(_) => {
-                let data_len = get_tag_length(tag);
-                label_len += data_len + varint::length(data_len as u64);
-            }
-            None => {}
-        }
-    }
-    return label_len;
+    DEFAULT_OPTIONS.labels.labels(tags).iter().fold(1, |acc, label| {
+        acc + label.len() + varint::length(label.len() as u64)
+    })
 }
 
+/// Priority of the default rule matching `tag` on its own, or `None` when no
+/// rule matches. Conditional rules are evaluated as if `tag` were the only tag.
 pub fn get_tag_priority(tag: &(&str, &str)) -> Option<u64> {
-    lazy_static! {
-        static ref ALL_PRIORITIES: Vec<(&'static str, u64)> = tag_priorities::get_priorities();
-    }
+    DEFAULT_OPTIONS.priorities.get(tag, &[*tag])
+}
 
-    let mut res: u64 = 0;
-    for (s, p) in ALL_PRIORITIES.iter() {
-        let wildcard = &format!("{}.*", tag.0);
-        let formatted_key: &str = &format!("{}.{}", tag.0, tag.1);
+/// A tag that maps to a known feature type, along with the priority it was
+/// ranked with.
+#[derive(Debug,Clone,PartialEq)]
+pub struct Candidate<'a> {
+    pub key: &'a str,
+    pub value: &'a str,
+    pub feature_type: u64,
+    pub priority: u64,
+}
 
-        if *s == formatted_key || s == wildcard {
-            if *p > res {
-                res = *p;
-            }
+/// Rank every tag that maps to a known feature type, best first. Candidates
+/// are ordered by descending priority, then by key and value so that the
+/// result does not depend on the order of `tags`. `parse` picks the first
+/// candidate, or `place.other` when there are none.
+pub fn classify<'a>(tags: &[(&'a str, &'a str)]) -> Vec<Candidate<'a>> {
+    classify_with(tags, &DEFAULT_OPTIONS.priorities)
+}
+
+/// Like `classify`, ranking with a custom set of priority rules.
+pub fn classify_with<'a>(
+    tags: &[(&'a str, &'a str)],
+    priorities: &tag_priorities::Priorities,
+) -> Vec<Candidate<'a>> {
+    let mut candidates = vec![];
+    for tag in tags {
+        if let Some(this_type) = osm_types::get_type(tag.0, tag.1) {
+            candidates.push(Candidate {
+                key: tag.0,
+                value: tag.1,
+                feature_type: this_type,
+                priority: priorities.priority(tag, tags),
+            });
         }
     }
-    return Some(res.clone());
+    candidates.sort_by(|a,b| {
+        b.priority.cmp(&a.priority)
+            .then_with(|| a.key.cmp(b.key))
+            .then_with(|| a.value.cmp(b.value))
+    });
+    candidates
 }
 
-pub fn parse(tags: &[(&str, &str)]) -> Result<(u64, Vec<u8>), Error> {
-    lazy_static! {
-        static ref RE: Regex = Regex::new("^(|[^:]+_)name($|:)").unwrap();
-        static ref ALL_TYPES: HashMap<&'static str, u64> = osm_types::get_types();
-        static ref DEFAULT_PRIORITY: u64 = 50;
+/// Settings for turning tags into a feature type and labels.
+#[derive(Debug,Clone,Default)]
+pub struct Options {
+    pub priorities: tag_priorities::Priorities,
+    pub labels: label::LabelRules,
+    /// Parse numeric attributes such as `height` and `layer` from tags. Off by
+    /// default because records with attributes use extended type bytes.
+    pub attributes: bool,
+    /// Keys of tags to keep as dictionary references in the attribute
+    /// section, such as `dictionary::DEFAULT_TAG_KEYS`. Empty by default.
+    pub tag_keys: Vec<String>,
+    pub dictionary: Dictionary,
+    /// How positions are stored. Records in anything but lon/lat carry it in
+    /// their attribute section.
+    pub projection: Projection,
+}
+
+impl Options {
+    /// The attributes to encode for `tags`, empty unless enabled.
+    pub fn attributes(&self, tags: &[(&str, &str)]) -> Attributes {
+        let mut attrs = if self.attributes {
+            Attributes::from_tags(tags)
+        } else {
+            Attributes::default()
+        };
+        attrs.tags = Attributes::select_tags(tags, &self.tag_keys, &self.dictionary);
+        attrs.projection = self.projection;
+        attrs
     }
+}
 
-    let place_other: u64 = *ALL_TYPES.get("place.other").unwrap();
-    let mut label = vec![0u8; get_label_length(tags)];
-    let mut top_type = place_other;
-    let mut top_priority = 0;
-    let mut offset = 0;
+lazy_static! {
+    pub(crate) static ref DEFAULT_OPTIONS: Options = Options::default();
+}
 
-    for tag in tags {
-        let formatted_key: &str = &format!("{}.{}", tag.0, tag.1);
-        let priority: u64;
-
-        match ALL_TYPES.get(formatted_key) {
-            Some(this_type) => {
-                //println!("Found type {} for {}", this_type, formatted_key);
-                match get_tag_priority(tag) {
-                    Some(_priority) => {
-                        //println!("Found priority {} for {}", _priority, formatted_key);
-                        priority = _priority
-                    }
-                    None => {
-                        priority = DEFAULT_PRIORITY.clone();
-                        //println!("Using default priority {} for {}", priority, formatted_key);
-                    }
-                }
-
-                //println!("comparing {} top and {} priority", top_priority, priority);
-                if top_priority <= priority {
-                    top_priority = priority;
-                    top_type = *this_type;
-                    //println!("top priority {} {}", top_priority, top_type);
-                }
-            }
-            None => {
-                //println!("Found no type for {}", formatted_key);
-            }
-        }
+pub fn parse(tags: &[(&str, &str)]) -> Result<(u64, Vec<u8>), Error> {
+    parse_with(tags, &DEFAULT_OPTIONS)
+}
 
-        // skip all tags that aren't the name tag
-        let is_name_tag = RE.find(tag.0);
-        match is_name_tag {
-            Some(_) => {
-                let tag_length = get_tag_length(tag);
-
-                let maybe_offset =
-                    varint::encode(tag_length as u64, &mut label[offset..]);
-                match maybe_offset {
-                    Ok(is_offset) => {
-                        offset += is_offset;
-                    }
-                    Err(_) => {
-                        bail!("Failed to encode tag {}.{}", tag.0, tag.1);
-                    }
-                }
-                let tstr = get_tag(tag);
-                tstr.bytes().for_each(|b| {
-                    label[offset] = b;
-                    offset += 1;
-                });
-            }
-            None => {}
-        }
+/// Like `parse`, using custom priority and label rules.
+pub fn parse_with(tags: &[(&str, &str)], options: &Options) -> Result<(u64, Vec<u8>), Error> {
+    lazy_static! {
+        static ref PLACE_OTHER: u64 = osm_types::get_type("place", "other").unwrap();
     }
-
-    label[offset] = 0x00;
-
-    //println!("top type {}", top_type);
-    return Ok((top_type, label));
+    let top_type = classify_with(tags, &options.priorities).first()
+        .map(|c| c.feature_type)
+        .unwrap_or(*PLACE_OTHER);
+    let label = options.labels.encode(tags)?;
+    Ok((top_type, label))
 }
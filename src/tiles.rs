@@ -0,0 +1,666 @@
+use crate::projection::{self, Projection};
+use crate::{decode_ref, Area, Encode, Feature, FeatureRef, Line};
+use crate::blocks::BBox;
+use failure::Error;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+#[test]
+fn tile_coordinates() {
+    assert_eq!(Tile::containing((10.0, 10.0), 1), Tile { z: 1, x: 1, y: 0 });
+    assert_eq!(Tile::containing((-10.0, -10.0), 1), Tile { z: 1, x: 0, y: 1 });
+    assert_eq!(Tile::containing((13.4, 52.5), 10), Tile { z: 10, x: 550, y: 335 });
+    let bbox = Tile { z: 1, x: 1, y: 0 }.bbox();
+    assert_eq!((bbox.min_x, bbox.max_x, bbox.max_y), (0.0, 180.0, projection::MAX_LATITUDE as f32));
+    assert_eq!(bbox.min_y, 0.0);
+}
+
+#[test]
+fn tile_features() -> Result<(),Error> {
+    use crate::Point;
+    use desert::ToBytesLE;
+    let options = TilerOptions { min_zoom: 0, max_zoom: 1, clip: true, ..Default::default() };
+    let mut tiler = Tiler::new(options);
+    let point = Point::from_tags(1, (10.0, 10.0), &[("amenity", "cafe")])?;
+    tiler.add(&point.to_bytes_le()?)?;
+    let line = Line::from_tags(2, &[("highway", "primary")], &[-10.0, 10.0, 10.0, 10.0])?;
+    tiler.add(&line.to_bytes_le()?)?;
+    let mut area = Area::from_tags(3, &[("leisure", "park")])?;
+    area.push(&[-10.0, -10.0, 10.0, -10.0, 10.0, -5.0, -10.0, -5.0], &[]);
+    tiler.add(&area.to_bytes_le()?)?;
+    // crosses the antimeridian, so it lands on both sides at zoom 1
+    let ferry = Line::from_tags(4, &[("route", "ferry")], &[170.0, 5.0, 190.0, 5.0])?;
+    tiler.add(&ferry.to_bytes_le()?)?;
+
+    let manifest = tiler.manifest();
+    let count = |z, x, y| manifest.tiles.iter()
+        .find(|t| t.tile == Tile { z, x, y }).map(|t| t.count).unwrap_or(0);
+    // the ferry is split at the antimeridian even at zoom 0
+    assert_eq!(count(0, 0, 0), 5);
+    assert_eq!(count(1, 1, 0), 3); // the point and halves of both lines
+    assert_eq!(count(1, 0, 0), 2); // the other halves of both lines
+    assert_eq!(count(1, 0, 1), 1);
+    assert_eq!(count(1, 1, 1), 1);
+
+    let features = tiler.features(&Tile { z: 1, x: 0, y: 0 })?;
+    let lines = features.iter().filter_map(|f| match f {
+        Feature::Line(l) => Some((l.id, l.positions.clone())),
+        _ => None,
+    }).collect::<Vec<_>>();
+    assert_eq!(lines, vec![
+        (2, vec![-10.0, 10.0, 0.0, 10.0]),
+        (4, vec![-180.0, 5.0, -170.0, 5.0]),
+    ]);
+    match &tiler.features(&Tile { z: 1, x: 1, y: 1 })?[0] {
+        Feature::Area(a) => {
+            assert!(a.positions.chunks(2).all(|p| p[0] >= 0.0 && p[0] <= 10.0));
+            let area = crate::triangulate::mesh_area(&a.positions, &a.cells);
+            assert!((area - 50.0).abs() < 1e-3, "{}", area);
+        },
+        x => panic!["expected area, got {:?}", x],
+    }
+
+    let dir = std::env::temp_dir().join(format!("georender-tiles-{}", std::process::id()));
+    tiler.write_dir(&dir)?;
+    assert_eq!(std::fs::read(dir.join("1/1/0.pack"))?, tiler.tile(&Tile { z: 1, x: 1, y: 0 }).unwrap());
+    assert!(std::fs::read_to_string(dir.join("manifest.json"))?.contains("\"z\":1,\"x\":1,\"y\":0"));
+    std::fs::remove_dir_all(&dir)?;
+
+    let mut archive = vec![];
+    let manifest = tiler.write_archive(&mut archive)?;
+    for entry in manifest.tiles.iter() {
+        let start = entry.offset.unwrap() as usize;
+        assert_eq!(&archive[start..start + entry.length as usize], tiler.tile(&entry.tile).unwrap());
+    }
+    Ok(())
+}
+
+#[test]
+fn tiler_flush() -> Result<(),Error> {
+    use crate::Point;
+    use desert::ToBytesLE;
+    let options = TilerOptions { min_zoom: 1, max_zoom: 1, clip: true, ..Default::default() };
+    let mut tiler = Tiler::new(options);
+    // on the corner of four tiles, so it goes to the one south-east of it,
+    // as with Tile::containing
+    let corner = Point::from_tags(1, (0.0, 0.0), &[("amenity", "cafe")])?;
+    tiler.add(&corner.to_bytes_le()?)?;
+    assert_eq!(tiler.tiles().map(|(t,_)| *t).collect::<Vec<_>>(), vec![Tile::containing((0.0, 0.0), 1)]);
+
+    let dir = std::env::temp_dir().join(format!("georender-flush-{}", std::process::id()));
+    let mut sink = DirSink::new(&dir);
+    tiler.flush(&mut sink)?;
+    assert_eq!(tiler.buffered(), 0);
+    assert_eq!(tiler.tiles().count(), 0);
+    let point = Point::from_tags(2, (10.0, -10.0), &[("amenity", "cafe")])?;
+    tiler.add(&point.to_bytes_le()?)?;
+    assert!(tiler.buffered() > 0);
+    tiler.flush(&mut sink)?;
+    assert!(tiler.write_dir(&dir).is_err());
+    let manifest = tiler.manifest();
+    assert_eq!(manifest.tiles.len(), 1);
+    assert_eq!(manifest.tiles[0].count, 2);
+    let buf = std::fs::read(dir.join("1/1/1.pack"))?;
+    assert_eq!(buf.len() as u64, manifest.tiles[0].length);
+    assert_eq!(decode_tile(&buf)?, vec![Feature::Point(corner), Feature::Point(point)]);
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+#[test]
+fn line_tiles() -> Result<(),Error> {
+    use desert::ToBytesLE;
+    // a diagonal whose bbox covers every tile at zoom 2
+    let line = Line::from_tags(1, &[("highway", "primary")], &[-170.0, -80.0, 0.0, 0.0, 170.0, 80.0])?;
+    for clip in [false, true].iter() {
+        let options = TilerOptions { min_zoom: 2, max_zoom: 2, clip: *clip, ..Default::default() };
+        let mut tiler = Tiler::new(options);
+        tiler.add(&line.to_bytes_le()?)?;
+        let tiles = tiler.tiles().map(|(t,_)| *t).collect::<Vec<_>>();
+        assert!(!tiles.contains(&Tile { z: 2, x: 0, y: 0 }));
+        assert!(!tiles.contains(&Tile { z: 2, x: 3, y: 3 }));
+        assert!(tiles.contains(&Tile { z: 2, x: 1, y: 2 }));
+        assert!(tiles.contains(&Tile { z: 2, x: 2, y: 1 }));
+        // one run per tile, since the line is straight
+        assert!(tiles.iter().all(|t| tiler.features(t).unwrap().len() == 1));
+    }
+    Ok(())
+}
+
+#[test]
+fn simplify_line() {
+    let positions = vec![0.0, 0.0, 1.0, 0.01, 2.0, 0.0, 3.0, 1.0];
+    assert_eq!(simplify(&positions, 0.1), vec![0.0, 0.0, 2.0, 0.0, 3.0, 1.0]);
+    assert_eq!(simplify(&positions, 0.0), positions);
+}
+
+/// A Web Mercator tile. `y` counts down from the north edge.
+#[derive(Debug,Clone,Copy,PartialEq,Eq,PartialOrd,Ord,Hash)]
+pub struct Tile {
+    pub z: u8,
+    pub x: u32,
+    pub y: u32,
+}
+
+impl Tile {
+    /// The tile containing a lon/lat point.
+    pub fn containing(p: (f32, f32), z: u8) -> Self {
+        let (x,y) = projection::to_mercator(p);
+        let n = 1u64 << z;
+        let index = |v: f32| ((v as f64 * n as f64).floor().max(0.0) as u64).min(n - 1) as u32;
+        Tile { z, x: index(x), y: index(y) }
+    }
+    /// The tile's extent in lon/lat.
+    pub fn bbox(&self) -> BBox {
+        let n = (1u64 << self.z) as f32;
+        let (min_x, max_y) = projection::from_mercator((self.x as f32 / n, self.y as f32 / n));
+        let (max_x, min_y) = projection::from_mercator(((self.x + 1) as f32 / n, (self.y + 1) as f32 / n));
+        BBox { min_x, min_y, max_x, max_y }
+    }
+}
+
+#[derive(Debug,Clone,PartialEq)]
+pub struct TilerOptions {
+    pub min_zoom: u8,
+    pub max_zoom: u8,
+    /// Cut lines and areas at tile edges so each tile only holds what is in
+    /// it. Lines may be split into several records with the same id.
+    pub clip: bool,
+    /// Simplify lines with Douglas-Peucker to this tolerance, as a fraction
+    /// of the tile width at each zoom. Areas are not simplified.
+    pub simplify: Option<f32>,
+}
+
+impl Default for TilerOptions {
+    fn default() -> Self {
+        Self { min_zoom: 0, max_zoom: 14, clip: false, simplify: None }
+    }
+}
+
+/// Assigns encoded features to the tiles they touch at each zoom. Tiles are
+/// buffered in memory until `flush` hands them to a `TileSink`, so memory
+/// stays bounded when flushing whenever `buffered` grows too large.
+pub struct Tiler {
+    options: TilerOptions,
+    tiles: BTreeMap<Tile,(Vec<u8>,u64)>,
+    buffered: usize,
+    // record count and length of every tile, flushed or not
+    totals: BTreeMap<Tile,(u64,u64)>,
+    flushed: bool,
+}
+
+/// Receives tiles flushed by a `Tiler`. A tile flushed more than once gets
+/// its later records appended to what it already holds.
+pub trait TileSink {
+    fn append(&mut self, tile: &Tile, data: &[u8]) -> Result<(),Error>;
+}
+
+/// Writes tiles to `dir/z/x/y.pack`. Files from earlier runs are replaced the
+/// first time this sink writes their tile.
+pub struct DirSink {
+    dir: PathBuf,
+    written: HashSet<Tile>,
+}
+
+impl DirSink {
+    pub fn new<P: AsRef<Path>>(dir: P) -> Self {
+        Self { dir: dir.as_ref().to_path_buf(), written: HashSet::new() }
+    }
+    /// Write the manifest to `dir/manifest.json`.
+    pub fn write_manifest(&self, manifest: &Manifest) -> Result<(),Error> {
+        std::fs::create_dir_all(&self.dir)?;
+        std::fs::write(self.dir.join("manifest.json"), manifest.to_json())?;
+        Ok(())
+    }
+}
+
+impl TileSink for DirSink {
+    fn append(&mut self, tile: &Tile, data: &[u8]) -> Result<(),Error> {
+        let path = self.dir.join(tile.z.to_string()).join(tile.x.to_string());
+        std::fs::create_dir_all(&path)?;
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .write(true)
+            .append(self.written.contains(tile))
+            .truncate(!self.written.contains(tile))
+            .open(path.join(format!("{}.pack", tile.y)))?;
+        file.write_all(data)?;
+        self.written.insert(*tile);
+        Ok(())
+    }
+}
+
+/// Which tiles were written, with how many records and bytes each holds and,
+/// for archives, where it starts.
+#[derive(Debug,Clone,PartialEq)]
+pub struct Manifest {
+    pub min_zoom: u8,
+    pub max_zoom: u8,
+    pub tiles: Vec<TileEntry>,
+}
+
+#[derive(Debug,Clone,PartialEq)]
+pub struct TileEntry {
+    pub tile: Tile,
+    pub count: u64,
+    pub length: u64,
+    pub offset: Option<u64>,
+}
+
+impl Manifest {
+    pub fn to_json(&self) -> String {
+        let tiles = self.tiles.iter().map(|t| {
+            let bbox = t.tile.bbox();
+            let mut s = format!(
+                "{{\"z\":{},\"x\":{},\"y\":{},\"count\":{},\"length\":{},\"bbox\":[{},{},{},{}]",
+                t.tile.z, t.tile.x, t.tile.y, t.count, t.length,
+                bbox.min_x, bbox.min_y, bbox.max_x, bbox.max_y
+            );
+            if let Some(offset) = t.offset { s += &format!(",\"offset\":{}", offset) }
+            s + "}"
+        }).collect::<Vec<String>>();
+        format!(
+            "{{\"min_zoom\":{},\"max_zoom\":{},\"tiles\":[{}]}}",
+            self.min_zoom, self.max_zoom, tiles.join(",")
+        )
+    }
+}
+
+impl Tiler {
+    pub fn new(options: TilerOptions) -> Self {
+        Self { options, tiles: BTreeMap::new(), buffered: 0, totals: BTreeMap::new(), flushed: false }
+    }
+
+    /// Add an encoded record to every tile it touches. Empty records are
+    /// skipped.
+    pub fn add(&mut self, record: &[u8]) -> Result<(),Error> {
+        if record.is_empty() { return Ok(()) }
+        let feature_ref = decode_ref(record)?;
+        let projection = feature_ref.attributes()?.projection;
+        let bbox = BBox::of(&feature_ref);
+        // lines are decoded to find the tiles they actually cross
+        let is_line = matches!(feature_ref, FeatureRef::Line(_));
+        let needs_decode = self.options.clip || self.options.simplify.is_some() || is_line;
+        let feature = if needs_decode { Some(feature_ref.into_owned()?) } else { None };
+        for z in self.options.min_zoom..=self.options.max_zoom {
+            let feature = match (&feature, self.options.simplify) {
+                (Some(Feature::Line(line)), Some(tolerance)) => {
+                    let n = (1u64 << z) as f32;
+                    let width = match projection {
+                        Projection::LonLat => 360.0 / n,
+                        Projection::Mercator => 1.0 / n,
+                    };
+                    let mut line = line.clone();
+                    line.positions = simplify(&line.positions, tolerance * width);
+                    Some(Feature::Line(line))
+                },
+                (f, _) => f.clone(),
+            };
+            match (&feature, self.options.clip) {
+                (Some(f), true) => for (tile, part) in clip(f, &bbox, z, projection) {
+                    self.append_feature(tile, &part)?;
+                },
+                (f, _) => {
+                    let tiles = match f {
+                        Some(Feature::Line(line)) if line.positions.len() >= 4 => {
+                            clip_line(&line.positions, z, projection).into_keys().collect()
+                        },
+                        _ => covering(&bbox, z, projection).into_iter()
+                            .map(|(tile,_,_)| tile).collect::<BTreeSet<Tile>>(),
+                    };
+                    for tile in tiles {
+                        match f {
+                            Some(f) => self.append_feature(tile, f)?,
+                            None => self.append(tile, record),
+                        }
+                    }
+                },
+            }
+        }
+        Ok(())
+    }
+
+    fn append(&mut self, tile: Tile, record: &[u8]) {
+        let entry = self.tiles.entry(tile).or_insert_with(|| (vec![], 0));
+        entry.0.extend_from_slice(record);
+        entry.1 += 1;
+        let total = self.totals.entry(tile).or_insert((0, 0));
+        total.0 += 1;
+        total.1 += record.len() as u64;
+        self.buffered += record.len();
+    }
+
+    fn append_feature(&mut self, tile: Tile, feature: &Feature) -> Result<(),Error> {
+        let entry = self.tiles.entry(tile).or_insert_with(|| (vec![], 0));
+        let len = feature.write_into(&mut entry.0)?;
+        entry.1 += 1;
+        let total = self.totals.entry(tile).or_insert((0, 0));
+        total.0 += 1;
+        total.1 += len as u64;
+        self.buffered += len;
+        Ok(())
+    }
+
+    /// Bytes of records held in memory since the last `flush`.
+    pub fn buffered(&self) -> usize {
+        self.buffered
+    }
+
+    /// Hand every buffered tile to `sink` in z/x/y order and drop it from
+    /// memory. The manifest still counts flushed tiles.
+    pub fn flush<S: TileSink>(&mut self, sink: &mut S) -> Result<(),Error> {
+        for (tile,(buf,_)) in std::mem::take(&mut self.tiles) {
+            sink.append(&tile, &buf)?;
+        }
+        self.buffered = 0;
+        self.flushed = true;
+        Ok(())
+    }
+
+    /// The concatenated records of a buffered tile, if it has any.
+    pub fn tile(&self, tile: &Tile) -> Option<&[u8]> {
+        self.tiles.get(tile).map(|(buf,_)| buf.as_slice())
+    }
+
+    /// Decode the records of a buffered tile.
+    pub fn features(&self, tile: &Tile) -> Result<Vec<Feature>,Error> {
+        decode_tile(self.tile(tile).unwrap_or(&[]))
+    }
+
+    /// Every buffered tile with its concatenated records, in z/x/y order.
+    pub fn tiles(&self) -> impl Iterator<Item=(&Tile,&[u8])> {
+        self.tiles.iter().map(|(tile,(buf,_))| (tile, buf.as_slice()))
+    }
+
+    /// Every tile added so far, including flushed ones.
+    pub fn manifest(&self) -> Manifest {
+        Manifest {
+            min_zoom: self.options.min_zoom,
+            max_zoom: self.options.max_zoom,
+            tiles: self.totals.iter().map(|(tile,(count,length))| TileEntry {
+                tile: *tile, count: *count, length: *length, offset: None,
+            }).collect(),
+        }
+    }
+
+    /// Write each tile to `dir/z/x/y.pack` and the manifest to
+    /// `dir/manifest.json`. Use a `DirSink` with `flush` instead to write
+    /// tiles as they fill up.
+    pub fn write_dir<P: AsRef<Path>>(&self, dir: P) -> Result<Manifest,Error> {
+        self.check_unflushed()?;
+        let mut sink = DirSink::new(dir);
+        for (tile,(buf,_)) in self.tiles.iter() {
+            sink.append(tile, buf)?;
+        }
+        let manifest = self.manifest();
+        sink.write_manifest(&manifest)?;
+        Ok(manifest)
+    }
+
+    /// Write every tile back to back into `w`, returning a manifest with
+    /// each tile's offset. Store `manifest.to_json()` alongside the archive.
+    /// Tiles must be contiguous, so this needs every tile still buffered.
+    pub fn write_archive<W: Write>(&self, mut w: W) -> Result<Manifest,Error> {
+        self.check_unflushed()?;
+        let mut manifest = self.manifest();
+        let mut offset = 0;
+        for (entry,(buf,_)) in manifest.tiles.iter_mut().zip(self.tiles.values()) {
+            w.write_all(buf)?;
+            entry.offset = Some(offset);
+            offset += buf.len() as u64;
+        }
+        w.flush()?;
+        Ok(manifest)
+    }
+
+    fn check_unflushed(&self) -> Result<(),Error> {
+        if self.flushed {
+            failure::bail!["some tiles were already flushed to a sink"];
+        }
+        Ok(())
+    }
+}
+
+/// Decode the concatenated records of a tile.
+pub fn decode_tile(mut buf: &[u8]) -> Result<Vec<Feature>,Error> {
+    use desert::FromBytesLE;
+    let mut features = vec![];
+    while !buf.is_empty() {
+        let (s,feature) = Feature::from_bytes_le(buf)?;
+        features.push(feature);
+        buf = &buf[s..];
+    }
+    Ok(features)
+}
+
+// Tiles at zoom `z` touched by `bbox`, with each tile's extent in the
+// record's coordinates before wrapping and the longitude shift that wraps
+// unwrapped positions back into it.
+fn covering(bbox: &BBox, z: u8, projection: Projection) -> Vec<(Tile,BBox,f32)> {
+    let n = 1i64 << z;
+    let (x0,y0,x1,y1) = match projection {
+        Projection::LonLat => {
+            let (_,y0) = projection::to_mercator((0.0, bbox.max_y));
+            let (_,y1) = projection::to_mercator((0.0, bbox.min_y));
+            ((bbox.min_x as f64 + 180.0) / 360.0, y0 as f64, (bbox.max_x as f64 + 180.0) / 360.0, y1 as f64)
+        },
+        Projection::Mercator => (bbox.min_x as f64, bbox.min_y as f64, bbox.max_x as f64, bbox.max_y as f64),
+    };
+    let index = |v: f64| (v * n as f64).floor() as i64;
+    let clamp = |v: i64| v.max(0).min(n - 1);
+    let mut tiles = vec![];
+    for tx in index(x0)..=index(x1) {
+        for ty in clamp(index(y0))..=clamp(index(y1)) {
+            let k = tx.div_euclid(n);
+            let tile = Tile { z, x: tx.rem_euclid(n) as u32, y: ty as u32 };
+            let (rect, shift) = match projection {
+                Projection::LonLat => {
+                    let mut rect = tile.bbox();
+                    rect.min_x += 360.0 * k as f32;
+                    rect.max_x += 360.0 * k as f32;
+                    (rect, -360.0 * k as f32)
+                },
+                Projection::Mercator => {
+                    let s = 1.0 / n as f32;
+                    let rect = BBox {
+                        min_x: tx as f32 * s, max_x: (tx + 1) as f32 * s,
+                        min_y: ty as f32 * s, max_y: (ty + 1) as f32 * s,
+                    };
+                    (rect, -(k as f32))
+                },
+            };
+            tiles.push((tile, rect, shift));
+        }
+    }
+    tiles
+}
+
+// Cut a feature with bounding box `bbox` into the tiles it touches at zoom
+// `z`. Positions wrapped past the antimeridian are moved back into range.
+fn clip(feature: &Feature, bbox: &BBox, z: u8, projection: Projection) -> Vec<(Tile,Feature)> {
+    let mut parts = vec![];
+    match feature {
+        // a point's bbox covers exactly one tile, picked with the same
+        // half-open rule as `Tile::containing`
+        Feature::Point(p) => for (tile, _, shift) in covering(bbox, z, projection) {
+            let mut p = p.clone();
+            p.point.0 += shift;
+            parts.push((tile, Feature::Point(p)));
+        },
+        Feature::Line(line) => for (tile, runs) in clip_line(&line.positions, z, projection) {
+            for positions in runs {
+                parts.push((tile, Feature::Line(Line { positions, ..line.clone() })));
+            }
+        },
+        Feature::Area(area) => for (tile, mesh) in clip_mesh(&area.positions, &area.cells, z, projection) {
+            if mesh.cells.is_empty() { continue }
+            let (positions, cells) = (mesh.positions, mesh.cells);
+            parts.push((tile, Feature::Area(Area { positions, cells, ..area.clone() })));
+        },
+    }
+    parts
+}
+
+// Triangles clipped to one tile, sharing vertices by position.
+#[derive(Default)]
+struct Mesh {
+    positions: Vec<f32>,
+    cells: Vec<usize>,
+    index: HashMap<(u32,u32),usize>,
+}
+
+impl Mesh {
+    // Fan a convex polygon into triangles.
+    fn push(&mut self, poly: &[(f32, f32)]) {
+        let Mesh { positions, cells, index } = self;
+        let ids = poly.iter().map(|(x,y)| {
+            *index.entry((x.to_bits(), y.to_bits())).or_insert_with(|| {
+                positions.extend_from_slice(&[*x, *y]);
+                positions.len() / 2 - 1
+            })
+        }).collect::<Vec<usize>>();
+        for k in 1..ids.len()-1 {
+            if ids[0] != ids[k] && ids[k] != ids[k+1] && ids[0] != ids[k+1] {
+                cells.extend_from_slice(&[ids[0], ids[k], ids[k+1]]);
+            }
+        }
+    }
+}
+
+// Clip each triangle (Sutherland-Hodgman) to only the tiles its own bounding
+// box touches, so each triangle is cut against a handful of tiles rather
+// than every tile of the area.
+fn clip_mesh(positions: &[f32], cells: &[usize], z: u8, projection: Projection) -> BTreeMap<Tile,Mesh> {
+    let mut meshes: BTreeMap<Tile,Mesh> = BTreeMap::new();
+    for c in cells.chunks_exact(3) {
+        if c.iter().any(|i| i*2+1 >= positions.len()) { continue }
+        let triangle = c.iter().map(|i| (positions[i*2], positions[i*2+1])).collect::<Vec<_>>();
+        let mut bbox = BBox::default();
+        triangle.iter().for_each(|p| bbox.extend(*p));
+        for (tile, rect, shift) in covering(&bbox, z, projection) {
+            let mut poly = triangle.clone();
+            for edge in 0..4 {
+                if poly.is_empty() { break }
+                let input = std::mem::take(&mut poly);
+                for k in 0..input.len() {
+                    let (p, q) = (input[k], input[(k + 1) % input.len()]);
+                    let (pin, qin) = (inside(p, edge, &rect), inside(q, edge, &rect));
+                    if pin { poly.push(p) }
+                    if pin != qin { poly.push(intersect(p, q, edge, &rect)) }
+                }
+            }
+            if poly.len() < 3 { continue }
+            poly.iter_mut().for_each(|p| p.0 += shift);
+            meshes.entry(tile).or_default().push(&poly);
+        }
+    }
+    meshes
+}
+
+// Clip each segment to only the tiles its own bounding box touches, joining
+// consecutive pieces in a tile into runs, so a line lands only in the tiles
+// it crosses.
+fn clip_line(positions: &[f32], z: u8, projection: Projection) -> BTreeMap<Tile,Vec<Vec<f32>>> {
+    let mut runs: BTreeMap<Tile,(usize,Vec<Vec<f32>>)> = BTreeMap::new();
+    for (i,w) in positions.chunks_exact(2).collect::<Vec<_>>().windows(2).enumerate() {
+        let (a,b) = ((w[0][0], w[0][1]), (w[1][0], w[1][1]));
+        let mut bbox = BBox::default();
+        bbox.extend(a);
+        bbox.extend(b);
+        for (tile, rect, shift) in covering(&bbox, z, projection) {
+            let (p,q) = match clip_segment(a, b, &rect) {
+                Some(s) => s,
+                None => continue,
+            };
+            // only touches the tile's edge
+            if p == q && a != b { continue }
+            let (p,q) = ((p.0 + shift, p.1), (q.0 + shift, q.1));
+            let (last, parts) = runs.entry(tile).or_insert_with(|| (i, vec![]));
+            match parts.last_mut() {
+                Some(run) if *last + 1 == i && run.ends_with(&[p.0, p.1]) => {
+                    run.extend_from_slice(&[q.0, q.1]);
+                },
+                _ => parts.push(vec![p.0, p.1, q.0, q.1]),
+            }
+            *last = i;
+        }
+    }
+    runs.into_iter().map(|(tile,(_,parts))| (tile,parts)).collect()
+}
+
+fn clip_segment(a: (f32, f32), b: (f32, f32), rect: &BBox) -> Option<((f32, f32),(f32, f32))> {
+    let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+    let (mut t0, mut t1) = (0.0f32, 1.0f32);
+    let checks = [
+        (-dx, a.0 - rect.min_x), (dx, rect.max_x - a.0),
+        (-dy, a.1 - rect.min_y), (dy, rect.max_y - a.1),
+    ];
+    for (p,q) in checks.iter() {
+        if *p == 0.0 {
+            if *q < 0.0 { return None }
+        } else {
+            let r = q / p;
+            if *p < 0.0 { t0 = t0.max(r) } else { t1 = t1.min(r) }
+            if t0 > t1 { return None }
+        }
+    }
+    let at = |t: f32| if t == 0.0 { a } else if t == 1.0 { b } else { (a.0 + t * dx, a.1 + t * dy) };
+    Some((at(t0), at(t1)))
+}
+
+fn inside(p: (f32, f32), edge: u8, rect: &BBox) -> bool {
+    match edge {
+        0 => p.0 >= rect.min_x,
+        1 => p.0 <= rect.max_x,
+        2 => p.1 >= rect.min_y,
+        _ => p.1 <= rect.max_y,
+    }
+}
+
+fn intersect(p: (f32, f32), q: (f32, f32), edge: u8, rect: &BBox) -> (f32, f32) {
+    match edge {
+        0 | 1 => {
+            let x = if edge == 0 { rect.min_x } else { rect.max_x };
+            (x, p.1 + (q.1 - p.1) * (x - p.0) / (q.0 - p.0))
+        },
+        _ => {
+            let y = if edge == 2 { rect.min_y } else { rect.max_y };
+            (p.0 + (q.0 - p.0) * (y - p.1) / (q.1 - p.1), y)
+        },
+    }
+}
+
+/// Douglas-Peucker simplification of flat `[x0,y0,x1,y1,...]` positions,
+/// keeping the end points.
+pub fn simplify(positions: &[f32], tolerance: f32) -> Vec<f32> {
+    let n = positions.len() / 2;
+    if n < 3 || tolerance <= 0.0 { return positions.to_vec() }
+    let p = |i: usize| (positions[i*2], positions[i*2+1]);
+    let mut keep = vec![false; n];
+    keep[0] = true;
+    keep[n-1] = true;
+    let mut stack = vec![(0, n-1)];
+    while let Some((first, last)) = stack.pop() {
+        let (a, b) = (p(first), p(last));
+        let (mut max, mut index) = (0.0, first);
+        for i in first+1..last {
+            let d = segment_distance(p(i), a, b);
+            if d > max { max = d; index = i }
+        }
+        if max > tolerance {
+            keep[index] = true;
+            stack.push((first, index));
+            stack.push((index, last));
+        }
+    }
+    (0..n).filter(|i| keep[*i]).flat_map(|i| vec![positions[i*2], positions[i*2+1]]).collect()
+}
+
+fn segment_distance(p: (f32, f32), a: (f32, f32), b: (f32, f32)) -> f32 {
+    let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+    let len = dx * dx + dy * dy;
+    let t = if len > 0.0 { (((p.0 - a.0) * dx + (p.1 - a.1) * dy) / len).clamp(0.0, 1.0) } else { 0.0 };
+    let (x, y) = (a.0 + t * dx - p.0, a.1 + t * dy - p.1);
+    (x * x + y * y).sqrt()
+}
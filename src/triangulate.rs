@@ -0,0 +1,220 @@
+use crate::winding;
+use failure::Error;
+use std::collections::HashMap;
+
+#[test]
+fn scanline_square_with_hole() {
+    let positions: Vec<f32> = vec![
+        0.0, 0.0, 4.0, 0.0, 4.0, 4.0, 0.0, 4.0,
+        1.0, 1.0, 1.0, 2.0, 2.0, 2.0, 2.0, 1.0,
+    ];
+    let (points, cells) = scanline(&positions, &[4]).unwrap();
+    assert_eq!(mesh_area(&points, &cells), 15.0);
+    assert_eq!(ring_area(&positions, &[4]), 15.0);
+    assert_eq!(coverage(&positions, &[4], &earcut(&positions, &[4])), 1.0);
+}
+
+#[test]
+fn scanline_bowtie() {
+    // a self-intersecting ring: earcut covers none or part of it
+    let positions: Vec<f32> = vec![0.0, 0.0, 2.0, 2.0, 2.0, 0.0, 0.0, 2.0];
+    let (points, cells) = scanline(&positions, &[]).unwrap();
+    assert_eq!(mesh_area(&points, &cells), 2.0);
+    assert!(cells.chunks(3).all(|c| {
+        winding::signed_area(&[
+            points[c[0]*2], points[c[0]*2+1], points[c[1]*2], points[c[1]*2+1],
+            points[c[2]*2], points[c[2]*2+1],
+        ]) > 0.0
+    }));
+}
+
+#[test]
+fn scanline_rejects_bad_input() {
+    let positions: Vec<f32> = vec![0.0, 0.0, 2.0, f32::NAN, 2.0, 0.0, 0.0, 2.0];
+    assert!(scanline(&positions, &[]).is_err());
+    let positions = (0..MAX_SCANLINE_EDGES + 1)
+        .flat_map(|i| vec![(i % 2) as f32, i as f32])
+        .collect::<Vec<f32>>();
+    assert!(scanline(&positions, &[]).is_err());
+}
+
+/// Triangulate rings with earcut. `holes` holds the position index each
+/// inner ring starts at.
+pub fn earcut(positions: &[f32], holes: &[usize]) -> Vec<usize> {
+    earcutr::earcut(
+        &positions.iter().map(|p| *p as f64).collect(),
+        &holes.to_vec(),
+        2
+    )
+}
+
+/// Planar area of the outer ring less its holes, ignoring winding.
+pub fn ring_area(positions: &[f32], holes: &[usize]) -> f64 {
+    let n = positions.len() / 2;
+    let starts = std::iter::once(0).chain(holes.iter().copied());
+    let ends = holes.iter().copied().chain(std::iter::once(n));
+    starts.zip(ends).enumerate()
+        .filter(|(_,(start,end))| start < end && *end <= n)
+        .map(|(i,(start,end))| {
+            let a = winding::signed_area(&positions[start*2..end*2]).abs() / 2.0;
+            if i == 0 { a } else { -a }
+        })
+        .sum()
+}
+
+/// Planar area covered by the triangles in `cells`.
+pub fn mesh_area(positions: &[f32], cells: &[usize]) -> f64 {
+    cells.chunks_exact(3)
+        .filter(|c| c.iter().all(|i| i*2+1 < positions.len()))
+        .map(|c| winding::signed_area(&[
+            positions[c[0]*2], positions[c[0]*2+1],
+            positions[c[1]*2], positions[c[1]*2+1],
+            positions[c[2]*2], positions[c[2]*2+1],
+        ]).abs() / 2.0)
+        .sum()
+}
+
+/// The triangle area of a triangulation relative to the ring area: 1 when it
+/// is complete, less when triangles are missing and more when they overlap
+/// or spill outside, as with self-intersecting rings.
+pub fn coverage(positions: &[f32], holes: &[usize], cells: &[usize]) -> f64 {
+    let expected = ring_area(positions, holes);
+    let actual = mesh_area(positions, cells);
+    if expected <= 0.0 {
+        return if actual <= 0.0 { 1.0 } else { f64::INFINITY };
+    }
+    actual / expected
+}
+
+/// Most edges `scanline` takes on. Rings with more are refused rather than
+/// risking quadratic work on crossings between badly broken rings.
+pub const MAX_SCANLINE_EDGES: usize = 100_000;
+
+/// Triangulate rings by cutting them into horizontal slabs at every vertex
+/// and crossing, filling between edges with the even-odd rule. Slower than
+/// earcut and it adds vertices, but it handles self-intersecting,
+/// overlapping and badly nested rings. Returns new positions and cells.
+/// Fails on positions that aren't finite or rings with more than
+/// `MAX_SCANLINE_EDGES` edges.
+pub fn scanline(positions: &[f32], holes: &[usize]) -> Result<(Vec<f32>, Vec<usize>),Error> {
+    if positions.iter().any(|p| !p.is_finite()) {
+        failure::bail!["cannot triangulate positions that are not finite"];
+    }
+    let n = positions.len() / 2;
+    let p = |i: usize| (positions[i*2] as f64, positions[i*2+1] as f64);
+    let mut edges = vec![];
+    let starts = std::iter::once(0).chain(holes.iter().copied());
+    let ends = holes.iter().copied().chain(std::iter::once(n));
+    for (start,end) in starts.zip(ends) {
+        if end < start + 3 || end > n { continue }
+        for i in start..end {
+            let (a,b) = (p(i), p(if i+1 == end { start } else { i+1 }));
+            if a.1 == b.1 { continue }
+            edges.push(if a.1 < b.1 { (a,b) } else { (b,a) });
+        }
+    }
+    if edges.len() > MAX_SCANLINE_EDGES {
+        failure::bail!["too many edges to triangulate: {} > {}", edges.len(), MAX_SCANLINE_EDGES];
+    }
+    edges.sort_by(|a: &Edge, b: &Edge| (a.0).1.total_cmp(&(b.0).1));
+
+    // sweep upwards, checking each edge for crossings only against the edges
+    // whose y range overlaps its own
+    let mut ys = edges.iter().flat_map(|(a,b)| vec![a.1, b.1]).collect::<Vec<f64>>();
+    let mut active: Vec<Edge> = vec![];
+    for e in edges.iter() {
+        active.retain(|(_,b)| b.1 > (e.0).1);
+        ys.extend(active.iter().filter_map(|a| crossing(*a, *e)));
+        active.push(*e);
+    }
+    ys.sort_by(|a,b| a.total_cmp(b));
+    ys.dedup();
+
+    let mut points = vec![];
+    let mut cells = vec![];
+    let mut index: HashMap<(u32,u32),usize> = HashMap::new();
+    let mut vertex = |x: f64, y: f64, points: &mut Vec<f32>| -> usize {
+        let (x,y) = (x as f32, y as f32);
+        *index.entry((x.to_bits(), y.to_bits())).or_insert_with(|| {
+            points.push(x);
+            points.push(y);
+            points.len() / 2 - 1
+        })
+    };
+    let mut active: Vec<Edge> = vec![];
+    let mut next = 0;
+    for w in ys.windows(2) {
+        let (y0,y1) = (w[0], w[1]);
+        let ym = (y0 + y1) / 2.0;
+        active.retain(|(_,b)| b.1 >= y1);
+        while next < edges.len() && (edges[next].0).1 <= y0 {
+            if (edges[next].1).1 >= y1 { active.push(edges[next]) }
+            next += 1;
+        }
+        let mut slab = active.iter()
+            .map(|e| (x_at(*e, y0), x_at(*e, ym), x_at(*e, y1)))
+            .collect::<Vec<_>>();
+        slab.sort_by(|a,b| a.1.total_cmp(&b.1));
+        for pair in slab.chunks_exact(2) {
+            let (l,r) = (pair[0], pair[1]);
+            let l0 = vertex(l.0, y0, &mut points);
+            let r0 = vertex(r.0, y0, &mut points);
+            let r1 = vertex(r.2, y1, &mut points);
+            let l1 = vertex(l.2, y1, &mut points);
+            if l0 != r0 { cells.extend_from_slice(&[l0, r0, r1]) }
+            if l1 != r1 { cells.extend_from_slice(&[l0, r1, l1]) }
+        }
+    }
+    Ok((points, cells))
+}
+
+type Edge = ((f64,f64),(f64,f64));
+
+fn x_at((a,b): Edge, y: f64) -> f64 {
+    if y <= a.1 { return a.0 }
+    if y >= b.1 { return b.0 }
+    a.0 + (b.0 - a.0) * (y - a.1) / (b.1 - a.1)
+}
+
+// y of the point where two edges properly cross, if they do
+fn crossing((a,b): Edge, (c,d): Edge) -> Option<f64> {
+    let r = (b.0 - a.0, b.1 - a.1);
+    let s = (d.0 - c.0, d.1 - c.1);
+    let denom = r.0 * s.1 - r.1 * s.0;
+    if denom == 0.0 { return None }
+    let t = ((c.0 - a.0) * s.1 - (c.1 - a.1) * s.0) / denom;
+    let u = ((c.0 - a.0) * r.1 - (c.1 - a.1) * r.0) / denom;
+    if t > 0.0 && t < 1.0 && u > 0.0 && u < 1.0 {
+        Some(a.1 + t * r.1)
+    } else {
+        None
+    }
+}
+
+/// How far `coverage` may stray from 1 before earcut's triangles are
+/// rejected.
+pub const TOLERANCE: f64 = 1e-3;
+
+/// How `Area::push_checked` triangulated a polygon.
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub enum Triangulation {
+    /// earcut covered the rings
+    Earcut,
+    /// earcut's triangles covered `coverage` times the ring area, so the rings
+    /// were triangulated with `scanline` instead. Usually a sign of broken
+    /// data.
+    Fallback { coverage: f64 },
+}
+
+impl Triangulation {
+    /// The worse of two results, for features made of several polygons: any
+    /// fallback, and of two fallbacks the one further from full coverage.
+    pub fn worst(self, other: Self) -> Self {
+        match (self, other) {
+            (Triangulation::Earcut, x) | (x, Triangulation::Earcut) => x,
+            (Triangulation::Fallback { coverage: a }, Triangulation::Fallback { coverage: b }) => {
+                if (b - 1.0).abs() > (a - 1.0).abs() || b.is_nan() { other } else { self }
+            },
+        }
+    }
+}
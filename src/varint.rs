@@ -37,5 +37,5 @@ pub fn encode(value: u64, buf: &mut [u8]) -> Result<usize, Error> {
 
 pub fn length(value: u64) -> usize {
     let msb = (64 - value.leading_zeros()) as usize;
-    (msb.max(1) + 6) / 7
+    msb.max(1).div_ceil(7)
 }
@@ -0,0 +1,66 @@
+#[test]
+fn normalize_winding() {
+    // clockwise outer with a counter-clockwise hole
+    let mut positions: Vec<f32> = vec![
+        0.0, 0.0, 0.0, 4.0, 4.0, 4.0, 4.0, 0.0,
+        1.0, 1.0, 2.0, 1.0, 2.0, 2.0,
+    ];
+    assert!(signed_area(&positions[0..8]) < 0.0);
+    normalize(&mut positions, &[4]);
+    assert_eq!(positions, vec![
+        0.0, 0.0, 4.0, 0.0, 4.0, 4.0, 0.0, 4.0,
+        1.0, 1.0, 2.0, 2.0, 2.0, 1.0,
+    ]);
+    assert!(is_ccw(&positions[0..8]));
+    assert!(!is_ccw(&positions[8..]));
+    let before = positions.clone();
+    normalize(&mut positions, &[4]);
+    assert_eq!(positions, before);
+}
+
+/// Twice the signed area of a ring of flat `[x0,y0,x1,y1,...]` positions.
+/// Positive for counter-clockwise rings with y pointing up, as with lon/lat.
+pub fn signed_area(ring: &[f32]) -> f64 {
+    let n = ring.len() / 2;
+    let mut sum = 0.0;
+    for i in 0..n {
+        let j = (i + 1) % n;
+        let (x0, y0) = (ring[i*2] as f64, ring[i*2+1] as f64);
+        let (x1, y1) = (ring[j*2] as f64, ring[j*2+1] as f64);
+        sum += x0 * y1 - x1 * y0;
+    }
+    sum
+}
+
+pub fn is_ccw(ring: &[f32]) -> bool {
+    signed_area(ring) > 0.0
+}
+
+/// Reverse the direction of a ring in place, keeping its first vertex first.
+pub fn reverse(ring: &mut [f32]) {
+    let n = ring.len() / 2;
+    if n < 3 { return }
+    for i in 1..n.div_ceil(2) {
+        let j = n - i;
+        ring.swap(i*2, j*2);
+        ring.swap(i*2+1, j*2+1);
+    }
+}
+
+/// Orient the outer ring of `positions` counter-clockwise and every hole
+/// clockwise. `holes` holds the position index each hole starts at, as passed
+/// to `Area::push`. Rings with no area are left alone.
+pub fn normalize(positions: &mut [f32], holes: &[usize]) {
+    let n = positions.len() / 2;
+    let starts = std::iter::once(0).chain(holes.iter().copied());
+    let ends = holes.iter().copied().chain(std::iter::once(n));
+    for (i,(start,end)) in starts.zip(ends).enumerate() {
+        if start >= end || end > n { continue }
+        let ring = &mut positions[start*2..end*2];
+        let area = signed_area(ring);
+        let outer = i == 0;
+        if (outer && area < 0.0) || (!outer && area > 0.0) {
+            reverse(ring);
+        }
+    }
+}
@@ -0,0 +1,54 @@
+use failure::Error;
+use std::io;
+
+#[test]
+fn write_into_failure() {
+    struct Broken;
+    impl Encode for Broken {
+        fn encoded_len(&self) -> usize { 8 }
+        fn write_to(&self, _buf: &mut [u8]) -> Result<usize,Error> {
+            failure::bail!["broken"]
+        }
+    }
+    let mut buf = vec![1, 2, 3];
+    assert!(Broken.write_into(&mut buf).is_err());
+    assert_eq!(buf, vec![1, 2, 3]);
+}
+
+/// Write encoded features into caller-provided storage so that many features
+/// can be packed into one reusable buffer.
+pub trait Encode {
+    /// Number of bytes `write_to` will write.
+    fn encoded_len(&self) -> usize;
+
+    /// Write into the front of `buf`, returning the number of bytes written.
+    /// Fails if `buf` is shorter than `encoded_len()`.
+    fn write_to(&self, buf: &mut [u8]) -> Result<usize,Error>;
+
+    /// Append to the end of `buf`, returning the number of bytes written. On
+    /// failure `buf` is left as it was.
+    fn write_into(&self, buf: &mut Vec<u8>) -> Result<usize,Error> {
+        let start = buf.len();
+        buf.resize(start + self.encoded_len(), 0);
+        match self.write_to(&mut buf[start..]) {
+            Ok(s) => {
+                buf.truncate(start + s);
+                Ok(s)
+            },
+            Err(e) => {
+                buf.truncate(start);
+                Err(e)
+            },
+        }
+    }
+
+    /// Write to `w`, returning the number of bytes written. This encodes into
+    /// a temporary buffer first, so prefer `write_into` with a reused `Vec`
+    /// when packing many features.
+    fn write_io<W: io::Write>(&self, w: &mut W) -> Result<usize,Error> {
+        let mut buf = vec![0u8; self.encoded_len()];
+        let s = self.write_to(&mut buf)?;
+        w.write_all(&buf[0..s])?;
+        Ok(s)
+    }
+}
@@ -0,0 +1,103 @@
+use georender_pack::{decode, Feature, encode::way};
+use georender_pack::blocks::{self, BBox};
+use georender_pack::container::Header;
+use std::collections::HashMap;
+use std::io::Cursor;
+
+type Error = Box<dyn std::error::Error+Send+Sync>;
+
+// a simplified outline of Taveuni, Fiji, which the 180th meridian runs through
+fn taveuni() -> Result<Vec<u8>,Error> {
+    let tags = vec![("place", "island"), ("natural", "wood"), ("name", "Taveuni")];
+    let mut nodes = HashMap::new();
+    nodes.insert(1, (-179.84, -16.70));
+    nodes.insert(2, (-179.872, -16.805));
+    nodes.insert(3, (-179.996, -16.951));
+    nodes.insert(4, (179.90, -17.00));
+    nodes.insert(5, (179.932, -16.895));
+    nodes.insert(6, (-179.944, -16.749));
+    let refs = vec![1, 2, 3, 4, 5, 6, 1];
+    Ok(way(1, &tags, &refs, &nodes)?)
+}
+
+#[test] fn taveuni_area() -> Result<(),Error> {
+    let area = match decode(&taveuni()?)? {
+        Feature::Area(area) => area,
+        x => panic!["expected area, got {:?}", x],
+    };
+    let lons = area.positions.iter().step_by(2).copied().collect::<Vec<f32>>();
+    let min = lons.iter().copied().fold(f32::INFINITY, f32::min);
+    let max = lons.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+    assert!(max - min < 0.5, "{:?}", lons);
+    assert_eq!(area.cells.len(), 4*3);
+    // the real island is 434 km², a globe-spanning mesh would be millions
+    let km2 = area.area_m2() / 1e6;
+    assert!(km2 > 200.0 && km2 < 500.0, "{}", km2);
+    Ok(())
+}
+
+#[test] fn taveuni_blocks_query() -> Result<(),Error> {
+    let mut writer = blocks::Writer::new(Cursor::new(vec![]), &Header::default())?;
+    writer.write_record(&taveuni()?)?;
+    let buf = writer.finish()?.into_inner();
+    let reader = blocks::Reader::new(&buf)?;
+    // the western half of the island, east of the antimeridian
+    let east = BBox { min_x: 179.8, min_y: -17.1, max_x: 180.0, max_y: -16.6 };
+    // the eastern half, west of the antimeridian
+    let west = BBox { min_x: -180.0, min_y: -17.1, max_x: -179.8, max_y: -16.6 };
+    for query in [east, west].iter() {
+        assert_eq!(reader.blocks_in(query), vec![0]);
+        assert_eq!(reader.features_in(query)?.len(), 1);
+    }
+    let elsewhere = BBox { min_x: 170.0, min_y: -17.1, max_x: 179.0, max_y: -16.6 };
+    assert!(reader.features_in(&elsewhere)?.is_empty());
+    Ok(())
+}
+
+// the ferry route from Suva, Fiji to Apia, Samoa
+#[test] fn suva_apia_line() -> Result<(),Error> {
+    let tags = vec![("route", "ferry")];
+    let mut nodes = HashMap::new();
+    nodes.insert(1, (178.44, -18.14));
+    nodes.insert(2, (-179.0, -16.5));
+    nodes.insert(3, (-171.76, -13.83));
+    let line = match decode(&way(2, &tags, &[1, 2, 3], &nodes)?)? {
+        Feature::Line(line) => line,
+        x => panic!["expected line, got {:?}", x],
+    };
+    assert_eq!(line.positions, vec![178.44, -18.14, 181.0, -16.5, 188.24, -13.83]);
+    let km = line.length_m() / 1e3;
+    assert!(km > 1100.0 && km < 1300.0, "{}", km);
+    Ok(())
+}
+
+// Vanua Levu and a made-up islet east of it, each drawn on its own side of
+// the 180th meridian as two outer rings of one multipolygon
+#[test] fn fiji_multipolygon() -> Result<(),Error> {
+    use georender_pack::{encode::relation, Member, MemberRole, MemberType};
+    let mut nodes = HashMap::new();
+    nodes.insert(1, (179.0, -16.2));
+    nodes.insert(2, (179.9, -16.5));
+    nodes.insert(3, (179.4, -16.9));
+    nodes.insert(4, (178.6, -16.7));
+    nodes.insert(11, (-179.9, -16.6));
+    nodes.insert(12, (-179.7, -16.6));
+    nodes.insert(13, (-179.8, -16.8));
+    let mut ways = HashMap::new();
+    ways.insert(100, vec![1, 2, 3, 4, 1]);
+    ways.insert(101, vec![11, 12, 13, 11]);
+    let tags = vec![("type", "multipolygon"), ("place", "island")];
+    let members = vec![
+        Member::new(100, MemberRole::Outer(), MemberType::Way()),
+        Member::new(101, MemberRole::Outer(), MemberType::Way()),
+    ];
+    let area = match decode(&relation(1, &tags, &members, &nodes, &ways)?)? {
+        Feature::Area(area) => area,
+        x => panic!["expected area, got {:?}", x],
+    };
+    // the islet is moved east of the antimeridian, next to Vanua Levu
+    let lons = area.positions.iter().step_by(2).copied().collect::<Vec<f32>>();
+    assert!(lons.iter().all(|x| *x > 178.0 && *x < 181.0), "{:?}", lons);
+    assert_eq!(area.cells.len(), 3*3);
+    Ok(())
+}
@@ -6,6 +6,9 @@ use georender_pack::{
 use std::collections::HashMap;
 use pretty_assertions::assert_eq;
 
+// Expected positions follow `winding::normalize`: outer rings counter-clockwise
+// and inner rings clockwise.
+
 type Error = Box<dyn std::error::Error+Send+Sync>;
 
 #[test] fn relation_area_from_parsed_0() -> Result<(),Error> {
@@ -23,8 +26,8 @@ type Error = Box<dyn std::error::Error+Send+Sync>;
     ways.insert(202, vec![104,105,106,104]);
 
     let positions = vec![
-        1.3, 1.2, 1.3, 0.3, -0.1, 0.3, -0.1, 1.2,
-        0.8, 0.7, 0.5, 0.5, 1.0, 0.6
+        1.3, 1.2, -0.1, 1.2, -0.1, 0.3, 1.3, 0.3,
+        0.8, 0.7, 1.0, 0.6, 0.5, 0.5,
     ];
     let cells = earcutr::earcut(&positions, &vec![4], 2);
     let feature_type = *get_types().get("natural.water").unwrap();
@@ -34,6 +37,7 @@ type Error = Box<dyn std::error::Error+Send+Sync>;
         labels: vec![0],
         positions: positions.iter().map(|p| *p as f32).collect(),
         cells,
+        attributes: Default::default(),
     });
     let members = vec![
         Member::new(200, MemberRole::Outer(), MemberType::Way()),
@@ -41,7 +45,7 @@ type Error = Box<dyn std::error::Error+Send+Sync>;
     ];
     assert_eq![&expected, &decode(&relation(1234, &tags, &members, &nodes, &ways)?)?];
     assert_eq![&expected, &decode(
-        &relation_from_parsed(1234, feature_type, true, &vec![0], &members, &nodes, &ways)?
+        &relation_from_parsed(1234, feature_type, true, &[0], &members, &nodes, &ways)?
     )?];
     Ok(())
 }
@@ -62,8 +66,8 @@ type Error = Box<dyn std::error::Error+Send+Sync>;
     ways.insert(202, vec![104,105,106,104]);
 
     let positions = vec![
-        1.3, 1.2, 1.3, 0.3, -0.1, 0.3, -0.1, 1.2,
-        0.8, 0.7, 0.5, 0.5, 1.0, 0.6
+        1.3, 1.2, -0.1, 1.2, -0.1, 0.3, 1.3, 0.3,
+        0.8, 0.7, 1.0, 0.6, 0.5, 0.5,
     ];
     let cells = earcutr::earcut(&positions, &vec![4], 2);
     let feature_type = *get_types().get("natural.water").unwrap();
@@ -73,6 +77,7 @@ type Error = Box<dyn std::error::Error+Send+Sync>;
         labels: vec![0],
         positions: positions.iter().map(|p| *p as f32).collect(),
         cells,
+        attributes: Default::default(),
     });
     let members = vec![
         Member::new(200, MemberRole::Outer(), MemberType::Way()),
@@ -81,7 +86,7 @@ type Error = Box<dyn std::error::Error+Send+Sync>;
     ];
     assert_eq![&expected, &decode(&relation(1234, &tags, &members, &nodes, &ways)?)?];
     assert_eq![&expected, &decode(
-        &relation_from_parsed(1234, feature_type, true, &vec![0], &members, &nodes, &ways)?
+        &relation_from_parsed(1234, feature_type, true, &[0], &members, &nodes, &ways)?
     )?];
     Ok(())
 }
@@ -103,8 +108,8 @@ type Error = Box<dyn std::error::Error+Send+Sync>;
     ways.insert(203, vec![105,106,104]);
 
     let positions = vec![
-        1.3, 1.2, 1.3, 0.3, -0.1, 0.3, -0.1, 1.2,
-        0.8, 0.7, 0.5, 0.5, 1.0, 0.6
+        1.3, 1.2, -0.1, 1.2, -0.1, 0.3, 1.3, 0.3,
+        0.8, 0.7, 1.0, 0.6, 0.5, 0.5,
     ];
     let cells = earcutr::earcut(&positions, &vec![4], 2);
     let feature_type = *get_types().get("natural.water").unwrap();
@@ -114,6 +119,7 @@ type Error = Box<dyn std::error::Error+Send+Sync>;
         labels: vec![0],
         positions: positions.iter().map(|p| *p as f32).collect(),
         cells,
+        attributes: Default::default(),
     });
     let members = vec![
         Member::new(200, MemberRole::Outer(), MemberType::Way()),
@@ -123,7 +129,7 @@ type Error = Box<dyn std::error::Error+Send+Sync>;
     ];
     assert_eq![&expected, &decode(&relation(1234, &tags, &members, &nodes, &ways)?)?];
     assert_eq![&expected, &decode(
-        &relation_from_parsed(1234, feature_type, true, &vec![0], &members, &nodes, &ways)?
+        &relation_from_parsed(1234, feature_type, true, &[0], &members, &nodes, &ways)?
     )?];
     Ok(())
 }
@@ -148,8 +154,8 @@ type Error = Box<dyn std::error::Error+Send+Sync>;
     ways.insert(206, vec![106,104]);
 
     let positions = vec![
-        1.3, 1.2, 1.3, 0.3, -0.1, 0.3, -0.1, 1.2,
-        0.8, 0.7, 0.5, 0.5, 1.0, 0.6
+        1.3, 1.2, -0.1, 1.2, -0.1, 0.3, 1.3, 0.3,
+        0.8, 0.7, 1.0, 0.6, 0.5, 0.5,
     ];
     let cells = earcutr::earcut(&positions, &vec![4], 2);
     let feature_type = *get_types().get("natural.water").unwrap();
@@ -159,6 +165,7 @@ type Error = Box<dyn std::error::Error+Send+Sync>;
         labels: vec![0],
         positions: positions.iter().map(|p| *p as f32).collect(),
         cells,
+        attributes: Default::default(),
     });
     let members = vec![
         Member::new(200, MemberRole::Outer(), MemberType::Way()),
@@ -171,7 +178,7 @@ type Error = Box<dyn std::error::Error+Send+Sync>;
     ];
     assert_eq![&expected, &decode(&relation(1234, &tags, &members, &nodes, &ways)?)?];
     assert_eq![&expected, &decode(
-        &relation_from_parsed(1234, feature_type, true, &vec![0], &members, &nodes, &ways)?
+        &relation_from_parsed(1234, feature_type, true, &[0], &members, &nodes, &ways)?
     )?];
     Ok(())
 }
@@ -196,8 +203,8 @@ type Error = Box<dyn std::error::Error+Send+Sync>;
     ways.insert(206, vec![106,104]);
 
     let positions = vec![
-        1.3, 0.3, -0.1, 0.3, -0.1, 1.2, 1.3, 1.2,
-        0.5, 0.5, 1.0, 0.6, 0.8, 0.7,
+        1.3, 0.3, 1.3, 1.2, -0.1, 1.2, -0.1, 0.3,
+        0.5, 0.5, 0.8, 0.7, 1.0, 0.6,
     ];
     let cells = earcutr::earcut(&positions, &vec![4], 2);
     let feature_type = *get_types().get("natural.water").unwrap();
@@ -207,6 +214,7 @@ type Error = Box<dyn std::error::Error+Send+Sync>;
         labels: vec![0],
         positions: positions.iter().map(|p| *p as f32).collect(),
         cells,
+        attributes: Default::default(),
     });
     let members = vec![
         Member::new(201, MemberRole::Outer(), MemberType::Way()),
@@ -219,7 +227,7 @@ type Error = Box<dyn std::error::Error+Send+Sync>;
     ];
     assert_eq![&expected, &decode(&relation(1234, &tags, &members, &nodes, &ways)?)?];
     assert_eq![&expected, &decode(
-        &relation_from_parsed(1234, feature_type, true, &vec![0], &members, &nodes, &ways)?
+        &relation_from_parsed(1234, feature_type, true, &[0], &members, &nodes, &ways)?
     )?];
     Ok(())
 }
@@ -245,7 +253,7 @@ type Error = Box<dyn std::error::Error+Send+Sync>;
 
     let positions = vec![
         -0.1, 0.3, 1.3, 0.3, 1.3, 1.2, -0.1, 1.2,
-        0.5, 0.5, 1.0, 0.6, 0.8, 0.7,
+        0.5, 0.5, 0.8, 0.7, 1.0, 0.6,
     ];
     let cells = earcutr::earcut(&positions, &vec![4], 2);
     let feature_type = *get_types().get("natural.water").unwrap();
@@ -255,6 +263,7 @@ type Error = Box<dyn std::error::Error+Send+Sync>;
         labels: vec![0],
         positions: positions.iter().map(|p| *p as f32).collect(),
         cells,
+        attributes: Default::default(),
     });
     let members = vec![
         Member::new(201, MemberRole::Outer(), MemberType::Way()),
@@ -267,7 +276,33 @@ type Error = Box<dyn std::error::Error+Send+Sync>;
     ];
     assert_eq![&expected, &decode(&relation(1234, &tags, &members, &nodes, &ways)?)?];
     assert_eq![&expected, &decode(
-        &relation_from_parsed(1234, feature_type, true, &vec![0], &members, &nodes, &ways)?
+        &relation_from_parsed(1234, feature_type, true, &[0], &members, &nodes, &ways)?
     )?];
     Ok(())
 }
+
+#[test] fn relation_area_from_osm_xml() -> Result<(),Error> {
+    use georender_pack::osm_xml::encode_str;
+    let xml = std::fs::read_to_string("tests/fixtures/mill-pond.osm")?;
+    let (records, skipped, fallbacks) = encode_str(&xml)?;
+    // way 2204 leads to a node outside the download
+    assert_eq!(skipped.iter().map(|s| s.id).collect::<Vec<u64>>(), vec![2204]);
+    assert!(fallbacks.is_empty());
+    let features = records.iter().map(|r| decode(r)).collect::<Result<Vec<Feature>,_>>()?;
+    // the deleted bench is left out
+    assert!(features.iter().all(|f| f.id() != 1110));
+    assert!(features.iter().any(|f| f.id() == 1111));
+    let area = match features.iter().find(|f| f.id() == 3301) {
+        Some(Feature::Area(area)) => area,
+        x => panic!["expected area, got {:?}", x],
+    };
+    assert_eq!(area.feature_type, *get_types().get("natural.water").unwrap());
+    // the outer ring is joined from two ways, with the islet as a hole
+    assert_eq!(area.positions.len(), 9*2);
+    let rings = area.rings();
+    assert_eq!(rings.iter().map(|r| r.len()).collect::<Vec<usize>>(), vec![6, 3]);
+    let covered = georender_pack::triangulate::mesh_area(&area.positions, &area.cells);
+    // 24e-6 square degrees of hexagon less a 2e-6 islet
+    assert!((covered - 22e-6).abs() < 1e-7, "{}", covered);
+    Ok(())
+}